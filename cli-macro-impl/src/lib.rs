@@ -32,11 +32,18 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
     let mut variants = og_enum.variants.clone();
     let mut commands = quote!();
 
+    // Commands that are already declared on the enum are implemented by hand, so we
+    // skip generating them.
+    let is_declared = |name: &str| og_enum.variants.iter().any(|v| v.ident == name);
+
+    // The delete command lists the resources to delete with `--all` and `--filter`.
+    let list_op = ops.iter().find(|op| op.is_root_list_operation(&params.tag));
+
     // Let's iterate over the paths and generate the code.
-    for op in ops {
+    for op in ops.iter() {
         // Let's generate the delete command if it exists.
-        if op.is_root_level_operation(&params.tag) && op.method == "DELETE" {
-            let (delete_cmd, delete_enum_item) = op.generate_delete_command(&params.tag)?;
+        if op.is_root_level_operation(&params.tag) && op.method == "DELETE" && !is_declared("Delete") {
+            let (delete_cmd, delete_enum_item) = op.generate_delete_command(&params.tag, list_op)?;
 
             commands = quote! {
                 #commands
@@ -47,7 +54,7 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // Clap with alphabetize the help text subcommands so it is fine to just shove
             // the variants on the end.
             variants.push(delete_enum_item);
-        } else if op.is_root_level_operation(&params.tag) && op.method == "GET" {
+        } else if op.is_root_level_operation(&params.tag) && op.method == "GET" && !is_declared("View") {
             let (view_cmd, view_enum_item) = op.generate_view_command(&params.tag)?;
 
             commands = quote! {
@@ -59,7 +66,7 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // Clap with alphabetize the help text subcommands so it is fine to just shove
             // the variants on the end.
             variants.push(view_enum_item);
-        } else if op.is_root_level_operation(&params.tag) && op.method == "PUT" {
+        } else if op.is_root_level_operation(&params.tag) && op.method == "PUT" && !is_declared("Edit") {
             let (edit_cmd, edit_enum_item) = op.generate_edit_command(&params.tag)?;
 
             commands = quote! {
@@ -71,7 +78,7 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // Clap with alphabetize the help text subcommands so it is fine to just shove
             // the variants on the end.
             variants.push(edit_enum_item);
-        } else if op.is_root_create_operation(&params.tag) {
+        } else if op.is_root_create_operation(&params.tag) && !is_declared("Create") {
             let (create_cmd, create_enum_item) = op.generate_create_command(&params.tag)?;
 
             commands = quote! {
@@ -83,7 +90,7 @@ pub fn do_gen(attr: TokenStream, item: TokenStream) -> Result<TokenStream> {
             // Clap with alphabetize the help text subcommands so it is fine to just shove
             // the variants on the end.
             variants.push(create_enum_item);
-        } else if op.is_root_list_operation(&params.tag) {
+        } else if op.is_root_list_operation(&params.tag) && !is_declared("List") {
             let (list_cmd, list_enum_item) = op.generate_list_command(&params.tag)?;
 
             commands = quote! {
@@ -663,6 +670,34 @@ impl Operation {
         false
     }
 
+    /// Returns true if the parameter is an `--organization` or `--project` flag, which falls back to
+    /// the current context when it is not passed. For the organizations and projects tags the
+    /// parameter is the resource itself, so it is not.
+    fn is_context_parameter(&self, parameter: &str, tag: &str) -> bool {
+        match parameter {
+            "organization" => self.is_parameter("organization") && tag != "organizations",
+            "project" => self.is_parameter("project") && tag != "projects",
+            _ => false,
+        }
+    }
+
+    /// Resolve the `--organization` and `--project` flags into variables of the same name,
+    /// falling back to the current context.
+    fn get_context_variables(&self, tag: &str) -> TokenStream {
+        let mut variables = quote!();
+        for p in ["organization", "project"] {
+            if self.is_context_parameter(p, tag) {
+                let ident = format_ident!("{}", p);
+                variables = quote! {
+                    #variables
+                    let #ident = ctx.#ident(&self.#ident)?;
+                };
+            }
+        }
+
+        variables
+    }
+
     fn get_request_body_name(&self) -> Result<String> {
         let request_body = match self.op.request_body.as_ref() {
             Some(r) => r,
@@ -794,6 +829,8 @@ impl Operation {
 
             p = clean_param_name(&p);
 
+            let is_context_parameter = self.is_context_parameter(&p, tag);
+
             let p = format_ident!("{}", p);
 
             if p == "sort_by" {
@@ -802,6 +839,12 @@ impl Operation {
                 continue;
             }
 
+            if is_context_parameter {
+                // This was resolved with `get_context_variables`.
+                api_call_params.push(quote!(&#p));
+                continue;
+            }
+
             api_call_params.push(quote!(&self.#p));
         }
 
@@ -1093,6 +1136,14 @@ impl Operation {
 
             let ident = format_ident!("{}", p);
 
+            if self.is_context_parameter(&p, tag) {
+                // Fall back to the current context, we prompt below if it is still empty.
+                mutable_variables.push(quote!(
+                    let mut #ident = ctx.context_value(#p, &self.#ident);
+                ));
+                continue;
+            }
+
             mutable_variables.push(quote!(
                 let mut #ident = self.#ident.clone();
             ));
@@ -1126,7 +1177,7 @@ impl Operation {
 
             required_checks.push(quote!(
                 if #p.#is_check() && !ctx.io.can_prompt() {
-                    return Err(anyhow::anyhow!(#error_msg));
+                    return Err(crate::errors::InvalidInputError(#error_msg.to_string()).into());
                 }
             ));
         }
@@ -1135,7 +1186,7 @@ impl Operation {
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub project: String,
             }
         } else {
@@ -1146,7 +1197,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub organization: String,
             }
         } else {
@@ -1367,7 +1418,7 @@ impl Operation {
 
                     #(#required_checks)*
 
-                    let client = ctx.api_client("")?;
+                    let client = ctx.api_client("").await?;
 
                     // Prompt for various parameters if we can, and the user passed them as empty.
                     if ctx.io.can_prompt() {
@@ -1417,12 +1468,13 @@ impl Operation {
         let struct_inner_name_doc = format!("The {} to edit. Can be an ID or name.", singular_tag_str);
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let context_variables = self.get_context_variables(tag);
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub project: String,
             }
         } else {
@@ -1433,7 +1485,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub organization: String,
             }
         } else {
@@ -1477,8 +1529,14 @@ impl Operation {
 
         // We need to form the output back to the client.
         let output = if self.is_parameter("organization") && self.is_parameter("project") {
+            // For projects, the project is the resource itself rather than a context variable.
+            let project = if tag == "projects" {
+                quote!(self.project)
+            } else {
+                quote!(project)
+            };
             let start = quote! {
-                let full_name = format!("{}/{}", self.organization, self.project);
+                let full_name = format!("{}/{}", organization, #project);
             };
             if tag != "projects" {
                 quote! {
@@ -1514,7 +1572,7 @@ impl Operation {
                             cs.success_icon(),
                             #singular_tag_str,
                             full_name,
-                            self.organization,
+                            organization,
                             self.new_name
                         )?;
                     } else {
@@ -1574,7 +1632,9 @@ impl Operation {
                 async fn run(&self, ctx: &mut crate::context::Context) -> anyhow::Result<()> {
                     #check_nothing_to_edit
 
-                    let client = ctx.api_client("")?;
+                    #context_variables
+
+                    let client = ctx.api_client("").await?;
 
                     let mut name = self.#singular_tag_lc.clone();
 
@@ -1620,12 +1680,13 @@ impl Operation {
         let struct_inner_name_doc = format!("The {} to view. Can be an ID or name.", singular_tag_str);
 
         let api_call_params = self.get_api_call_params(tag)?;
+        let context_variables = self.get_context_variables(tag);
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub project: String,
             }
         } else {
@@ -1636,7 +1697,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub organization: String,
             }
         } else {
@@ -1664,7 +1725,15 @@ impl Operation {
                 #[clap(short, long)]
                 pub web: bool,
 
-                /// Display output in json, yaml, or table format.
+                /// Refresh the output every `--interval` until interrupted.
+                #[clap(long)]
+                pub watch: bool,
+
+                /// How often to refresh with `--watch`, for example `500ms`, `2s`, or `1m`.
+                #[clap(long, default_value = "2s")]
+                pub interval: crate::types::Interval,
+
+                /// Display output in json, yaml, table, csv, or tsv format.
                 #[clap(long, short)]
                 pub format: Option<crate::types::FormatOutput>,
             }
@@ -1684,12 +1753,18 @@ impl Operation {
                         return Ok(());
                     }
 
-                    let client = ctx.api_client("")?;
+                    #context_variables
 
-                    let result = client.#tag_ident().get(#(#api_call_params),*).await?;
+                    let client = ctx.api_client("").await?;
 
                     let format = ctx.format(&self.format)?;
-                    ctx.io.write_output(&format, &result)?;
+                    let mut watch = crate::watch::Watch::new(self.watch, &self.interval);
+                    while watch.tick().await {
+                        let result = client.#tag_ident().get(#(#api_call_params),*).await?;
+
+                        watch.write_output(&mut ctx.io, &format, &result)?;
+                    }
+
                     Ok(())
                 }
             }
@@ -1717,29 +1792,14 @@ impl Operation {
         let struct_inner_project_doc = format!("The project that holds the {}.", plural(&singular_tag_str));
 
         let api_call_params = self.get_api_call_params(tag)?;
-
-        let mut api_call_params_all: Vec<TokenStream> = Vec::new();
-        for p in self.get_all_param_names()? {
-            if p == "limit" || p == "page_token" {
-                continue;
-            }
-
-            if p == "sort_by" {
-                api_call_params_all.push(quote!(self.sort_by.clone()));
-                continue;
-            }
-
-            let n = clean_param_name(&p);
-            let ident = format_ident!("{}", n);
-
-            api_call_params_all.push(quote!(&self.#ident));
-        }
+        let api_call_params_all = self.get_all_api_call_params(tag, quote!(self.sort_by.clone()))?;
+        let context_variables = self.get_context_variables(tag);
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub project: String,
             }
         } else {
@@ -1750,7 +1810,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub organization: String,
             }
         } else {
@@ -1778,7 +1838,15 @@ impl Operation {
                 #[clap(long)]
                 pub paginate: bool,
 
-                /// Display output in json, yaml, or table format.
+                /// Refresh the output every `--interval` until interrupted.
+                #[clap(long)]
+                pub watch: bool,
+
+                /// How often to refresh with `--watch`, for example `500ms`, `2s`, or `1m`.
+                #[clap(long, default_value = "2s")]
+                pub interval: crate::types::Interval,
+
+                /// Display output in json, yaml, table, csv, or tsv format.
                 #[clap(long, short)]
                 pub format: Option<crate::types::FormatOutput>,
             }
@@ -1790,26 +1858,32 @@ impl Operation {
                     return Err(anyhow::anyhow!("--limit must be greater than 0"));
                 }
 
-                let client = ctx.api_client("")?;
+                #context_variables
 
-                let results = if self.paginate {
-                    client
-                        .#tag_ident()
-                        .get_all(
-                            #(#api_call_params_all),*
-                        )
-                        .await?
-                } else {
-                    client
-                        .#tag_ident()
-                        .get_page(
-                            #(#api_call_params),*
-                        )
-                        .await?
-                };
+                let client = ctx.api_client("").await?;
 
                 let format = ctx.format(&self.format)?;
-                ctx.io.write_output_for_vec(&format, &results)?;
+                let mut watch = crate::watch::Watch::new(self.watch, &self.interval);
+                while watch.tick().await {
+                    let results = if self.paginate {
+                        client
+                            .#tag_ident()
+                            .get_all(
+                                #(#api_call_params_all),*
+                            )
+                            .await?
+                    } else {
+                        client
+                            .#tag_ident()
+                            .get_page(
+                                #(#api_call_params),*
+                            )
+                            .await?
+                    };
+
+                    watch.write_output_for_vec(&mut ctx.io, &format, &results)?;
+                }
+
                 Ok(())
             }
         }
@@ -1820,28 +1894,96 @@ impl Operation {
         Ok((cmd, enum_item))
     }
 
+    /// The parameters of the `get_all` call of a list operation, which leaves out the
+    /// `limit` and `page_token` of `get_page`.
+    fn get_all_api_call_params(&self, tag: &str, sort_by: TokenStream) -> Result<Vec<TokenStream>> {
+        let mut api_call_params_all: Vec<TokenStream> = Vec::new();
+        for p in self.get_all_param_names()? {
+            if p == "limit" || p == "page_token" {
+                continue;
+            }
+
+            if p == "sort_by" {
+                api_call_params_all.push(sort_by.clone());
+                continue;
+            }
+
+            let n = clean_param_name(&p);
+            let ident = format_ident!("{}", n);
+
+            if self.is_context_parameter(&n, tag) {
+                api_call_params_all.push(quote!(&#ident));
+                continue;
+            }
+
+            api_call_params_all.push(quote!(&self.#ident));
+        }
+
+        Ok(api_call_params_all)
+    }
+
     /// Generate the delete command.
-    fn generate_delete_command(&self, tag: &str) -> Result<(TokenStream, syn::Variant)> {
+    fn generate_delete_command(&self, tag: &str, list_op: Option<&Operation>) -> Result<(TokenStream, syn::Variant)> {
         let tag_ident = format_ident!("{}", tag);
         let singular_tag_str = if tag == "vpcs" {
             singular(tag).to_uppercase()
         } else {
             singular(tag)
         };
+        let plural_tag_str = plural(&singular_tag_str);
         let singular_tag_lc = format_ident!("{}", singular(tag));
         let struct_name = format_ident!("Cmd{}Delete", to_title_case(&singular(tag)));
 
-        let struct_doc = format!("Delete {}.", singular_tag_str);
-        let struct_inner_name_doc = format!("The {} to delete. Can be an ID or name.", singular_tag_str);
-        let struct_inner_project_doc = format!("The project to delete the {} from.", singular_tag_str);
-
-        let api_call_params = self.get_api_call_params(tag)?;
+        let struct_doc = if list_op.is_some() {
+            format!(
+                "Delete {}.\n\nPass the {} to delete by name or ID, or pick them with `--all` or `--filter`. A filter is a glob like `test-*`, or a regex between slashes like `/^test-\\d+$/`.\n\nAll of them are confirmed at once, then deleted `--parallel` at a time.",
+                plural_tag_str, plural_tag_str
+            )
+        } else {
+            format!(
+                "Delete {}.\n\nPass the {} to delete by name or ID. All of them are confirmed at once, then deleted `--parallel` at a time.",
+                plural_tag_str, plural_tag_str
+            )
+        };
+        let struct_inner_name_doc = format!("The {} to delete. Can be IDs or names.", plural_tag_str);
+        let struct_inner_project_doc = format!("The project to delete the {} from.", plural_tag_str);
+        let struct_inner_all_doc = format!("Delete all the {}.", plural_tag_str);
+        let struct_inner_filter_doc = format!("Delete the {} with names that match a glob or a regex.", plural_tag_str);
+        let missing_names_error = format!("{} to delete required, or use `--all` or `--filter`", singular_tag_str);
+
+        // The delete call runs for each name, so the resource is the name rather than
+        // the flag.
+        let resource_param = get_text(&quote!(&self.#singular_tag_lc))?;
+        let api_call_params: Vec<TokenStream> = self
+            .get_api_call_params(tag)?
+            .into_iter()
+            .map(|p| {
+                if get_text(&p).ok().as_deref() == Some(resource_param.as_str()) {
+                    quote!(&name)
+                } else {
+                    p
+                }
+            })
+            .collect();
+        let context_variables = self.get_context_variables(tag);
+
+        // The deletes run concurrently, so they borrow the variables they need.
+        let mut context_references = quote!(let client = &client;);
+        for p in ["organization", "project"] {
+            if self.is_context_parameter(p, tag) {
+                let ident = format_ident!("{}", p);
+                context_references = quote! {
+                    #context_references
+                    let #ident = &#ident;
+                };
+            }
+        }
 
         // We need to check if project is a parameter to this call.
         let project_param = if self.is_parameter("project") && tag != "projects" {
             quote! {
                 #[doc = #struct_inner_project_doc]
-                #[clap(long, short, required = true)]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub project: String,
             }
         } else {
@@ -1852,7 +1994,7 @@ impl Operation {
         let organization_param = if self.is_parameter("organization") && tag != "organizations" {
             quote! {
                 /// The organization that holds the project.
-                #[clap(long, short, required = true, env = "OXIDE_ORG")]
+                #[clap(long, short, default_value = "", hide_default_value = true)]
                 pub organization: String,
             }
         } else {
@@ -1861,44 +2003,82 @@ impl Operation {
 
         let additional_struct_params = self.get_additional_struct_params(tag)?;
 
+        // We can only pick the resources to delete with `--all` and `--filter` if we
+        // can list them.
+        let (selector_params, names) = if let Some(list_op) = list_op {
+            let api_call_params_all = list_op.get_all_api_call_params(tag, quote!(Default::default()))?;
+            (
+                quote! {
+                    #[doc = #struct_inner_all_doc]
+                    #[clap(long, conflicts_with = "filter")]
+                    pub all: bool,
+
+                    #[doc = #struct_inner_filter_doc]
+                    #[clap(long)]
+                    pub filter: Option<crate::types::NameFilter>,
+                },
+                quote! {
+                    let mut names = self.#singular_tag_lc.clone();
+                    if self.all || self.filter.is_some() {
+                        let all = client
+                            .#tag_ident()
+                            .get_all(
+                                #(#api_call_params_all),*
+                            )
+                            .await?;
+
+                        names = all
+                            .iter()
+                            .map(|item| item.name.to_string())
+                            .filter(|name| self.filter.as_ref().map(|f| f.is_match(name)).unwrap_or(true))
+                            .collect();
+                    } else if names.is_empty() {
+                        return Err(anyhow::anyhow!(#missing_names_error));
+                    }
+                },
+            )
+        } else {
+            (
+                quote!(),
+                quote! {
+                    let names = self.#singular_tag_lc.clone();
+                    if names.is_empty() {
+                        return Err(anyhow::anyhow!(#missing_names_error));
+                    }
+                },
+            )
+        };
+
+        let name_conflicts = if list_op.is_some() {
+            quote!(#[clap(name = #singular_tag_str, conflicts_with = "all")])
+        } else {
+            quote!(#[clap(name = #singular_tag_str)])
+        };
+
+        let filter_conflicts = if list_op.is_some() {
+            quote! {
+                if self.filter.is_some() && !self.#singular_tag_lc.is_empty() {
+                    return Err(anyhow::anyhow!("--filter cannot be used with {} names", #singular_tag_str));
+                }
+            }
+        } else {
+            quote!()
+        };
+
         // We need to form the output back to the client.
-        let output = if self.is_parameter("organization") && self.is_parameter("project") {
-            let start = quote! {
-                let full_name = format!("{}/{}", self.organization, self.project);
-            };
+        let describe = if self.is_parameter("organization") && self.is_parameter("project") {
             if tag != "projects" {
                 quote! {
-                    #start
-                    writeln!(
-                        ctx.io.out,
-                        "{} Deleted {} {} from {}",
-                        cs.success_icon_with_color(ansi_term::Color::Red),
-                        #singular_tag_str,
-                        self.#singular_tag_lc,
-                        full_name
-                    )?;
+                    let describe = |name: &str| format!("{} {} from {}/{}", #singular_tag_str, name, organization, project);
                 }
             } else {
                 quote! {
-                    #start
-                    writeln!(
-                        ctx.io.out,
-                        "{} Deleted {} {}",
-                        cs.success_icon_with_color(ansi_term::Color::Red),
-                        #singular_tag_str,
-                        full_name
-                    )?;
+                    let describe = |name: &str| format!("{} {}/{}", #singular_tag_str, organization, name);
                 }
             }
         } else {
             quote! {
-                writeln!(
-                    ctx.io.out,
-                    "{} Deleted {} {}",
-                    cs.success_icon_with_color(ansi_term::Color::Red),
-                    #singular_tag_str,
-                    self.#singular_tag_lc
-                )?;
+                let describe = |name: &str| format!("{} {}", #singular_tag_str, name);
             }
         };
 
@@ -1908,8 +2088,8 @@ impl Operation {
             #[clap(verbatim_doc_comment)]
             pub struct #struct_name {
                 #[doc = #struct_inner_name_doc]
-                #[clap(name = #singular_tag_str, required = true)]
-                pub #singular_tag_lc: String,
+                #name_conflicts
+                pub #singular_tag_lc: Vec<String>,
 
                 #project_param
 
@@ -1917,6 +2097,12 @@ impl Operation {
 
                 #(#additional_struct_params)*
 
+                #selector_params
+
+                /// How many to delete at once.
+                #[clap(long, default_value = "4")]
+                pub parallel: usize,
+
                 /// Confirm deletion without prompting.
                 #[clap(long)]
                 pub confirm: bool,
@@ -1929,37 +2115,35 @@ impl Operation {
                         return Err(anyhow::anyhow!("--confirm required when not running interactively"));
                     }
 
-                    let client = ctx.api_client("")?;
-
-
-                    // Confirm deletion.
-                    if !self.confirm {
-                        if let Err(err) = dialoguer::Input::<String>::new()
-                            .with_prompt(format!("Type {} to confirm deletion:", self.#singular_tag_lc))
-                            .validate_with(|input: &String| -> Result<(), &str> {
-                                if input.trim() == self.#singular_tag_lc {
-                                    Ok(())
-                                } else {
-                                    Err("mismatched confirmation")
-                                }
-                            })
-                            .interact_text()
-                        {
-                            return Err(anyhow::anyhow!("prompt failed: {}", err));
-                        }
-                    }
+                    #filter_conflicts
 
+                    #context_variables
 
-                    client
-                        .#tag_ident()
-                        .delete(#(#api_call_params),*)
-                        .await?;
+                    let client = ctx.api_client("").await?;
 
-                    let cs = ctx.io.color_scheme();
+                    #names
 
-                    #output
+                    #describe
 
-                    Ok(())
+                    #context_references
+
+                    crate::bulk::delete(
+                        ctx,
+                        #plural_tag_str,
+                        names,
+                        describe,
+                        self.confirm,
+                        self.parallel,
+                        |name| async move {
+                            client
+                                .#tag_ident()
+                                .delete(#(#api_call_params),*)
+                                .await?;
+
+                            Ok(())
+                        },
+                    )
+                    .await
                 }
             }
         );
@@ -11,7 +11,6 @@ fn test_do_gen() {
             #[derive(Parser, Debug, Clone)]
             enum SubCommand {
                 Attach(CmdDiskAttach),
-                Create(CmdDiskCreate),
                 Detach(CmdDiskDetach),
                 Edit(CmdDiskEdit),
             }
@@ -73,6 +72,21 @@ fn test_do_gen() {
 
     expectorate::assert_contents("tests/gen/sleds.rs.gen", &get_text_fmt(&actual).unwrap());
 
+    actual = do_gen(
+        quote! {
+            tag = "racks",
+        },
+        quote! {
+            #[derive(Parser, Debug, Clone)]
+            enum SubCommand {
+                View(CmdRackView),
+            }
+        },
+    )
+    .unwrap();
+
+    expectorate::assert_contents("tests/gen/racks.rs.gen", &get_text_fmt(&actual).unwrap());
+
     actual = do_gen(
         quote! {
             tag = "instances",
@@ -0,0 +1,583 @@
+use std::borrow::Borrow;
+
+use anyhow::{anyhow, Result};
+use openapiv3::{ReferenceOr, Schema, SchemaKind, Type};
+use thiserror::Error;
+
+/// The OpenAPI spec of the API that oxide was built against.
+const SPEC: &str = include_str!("../spec.json");
+
+/// Schemas nest in each other, sometimes recursively, so we stop describing them at
+/// this depth.
+const MAX_DEPTH: usize = 6;
+
+/// The methods of an endpoint, in the order we print them.
+const METHODS: [&str; 8] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE"];
+
+/// A request body that does not match the schema of its endpoint.
+#[derive(Error, Debug)]
+#[error("invalid request body: {}", .0.join(", "))]
+pub struct ValidationError(pub Vec<String>);
+
+/// The API spec that is embedded into oxide.
+pub struct Spec {
+    api: openapiv3::OpenAPI,
+}
+
+/// An operation of the API: a method on a path.
+#[derive(Debug, Clone)]
+pub struct Endpoint<'a> {
+    pub method: &'static str,
+    pub path: &'a str,
+    pub op: &'a openapiv3::Operation,
+}
+
+impl<'a> Endpoint<'a> {
+    /// The ID of the operation, like `organizations_get`.
+    pub fn id(&self) -> &'a str {
+        self.op.operation_id.as_deref().unwrap_or_default()
+    }
+
+    /// The tag the operation is grouped under, like `organizations`.
+    pub fn tag(&self) -> &'a str {
+        self.op.tags.first().map(|t| t.as_str()).unwrap_or_default()
+    }
+
+    /// Whether a path, like `/organizations/maze-war`, is a path of the endpoint. The
+    /// query string is ignored.
+    fn matches(&self, path: &str) -> bool {
+        let path = path.split('?').next().unwrap_or_default();
+        let path = format!("/{}", path.trim_start_matches('/'));
+        let want: Vec<&str> = self.path.split('/').collect();
+        let got: Vec<&str> = path.split('/').collect();
+
+        want.len() == got.len()
+            && want
+                .iter()
+                .zip(got.iter())
+                .all(|(w, g)| w == g || (w.starts_with('{') && !g.is_empty()))
+    }
+
+    /// The number of segments of the path that are not parameters, to prefer
+    /// `/organizations/{organization_name}/projects` over a path that is all parameters.
+    fn literal_segments(&self) -> usize {
+        self.path.split('/').filter(|s| !s.starts_with('{')).count()
+    }
+}
+
+/// A field of a request body, as it is set with `oxide api -F`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub key: String,
+    pub kind: String,
+    pub required: bool,
+    pub description: String,
+}
+
+impl Spec {
+    pub fn load() -> Result<Self> {
+        Ok(Spec {
+            api: serde_json::from_str(SPEC)?,
+        })
+    }
+
+    /// All the endpoints of the API, in the order of the spec.
+    pub fn endpoints(&self) -> Vec<Endpoint<'_>> {
+        let mut endpoints = Vec::new();
+        for (path, item) in self.api.paths.iter() {
+            let item = match item {
+                ReferenceOr::Item(item) => item,
+                ReferenceOr::Reference { .. } => continue,
+            };
+
+            let ops = [
+                &item.get,
+                &item.post,
+                &item.put,
+                &item.patch,
+                &item.delete,
+                &item.head,
+                &item.options,
+                &item.trace,
+            ];
+            for (method, op) in METHODS.iter().zip(ops) {
+                if let Some(op) = op {
+                    endpoints.push(Endpoint {
+                        method,
+                        path,
+                        op,
+                    });
+                }
+            }
+        }
+
+        endpoints
+    }
+
+    /// Find endpoints by their operation ID, like `organizations_get`, or by their path,
+    /// like `/organizations/{organization_name}` or `/organizations/maze-war`.
+    pub fn find(&self, query: &str) -> Vec<Endpoint<'_>> {
+        let by_id: Vec<Endpoint> = self.endpoints().into_iter().filter(|e| e.id() == query).collect();
+        if !by_id.is_empty() {
+            return by_id;
+        }
+
+        most_literal(self.endpoints().into_iter().filter(|e| e.matches(query)).collect())
+    }
+
+    /// The endpoint for a request, if it is in the spec.
+    pub fn endpoint(&self, method: &str, path: &str) -> Option<Endpoint<'_>> {
+        most_literal(
+            self.endpoints()
+                .into_iter()
+                .filter(|e| e.method.eq_ignore_ascii_case(method) && e.matches(path))
+                .collect(),
+        )
+        .into_iter()
+        .next()
+    }
+
+    /// The schema of the json request body of an endpoint, if it has one.
+    pub fn body_schema<'a>(&'a self, endpoint: &Endpoint<'a>) -> Result<Option<&'a Schema>> {
+        let body = match &endpoint.op.request_body {
+            Some(ReferenceOr::Item(body)) => body,
+            Some(ReferenceOr::Reference { reference }) => {
+                return Err(anyhow!("request body references are not supported: {}", reference))
+            }
+            None => return Ok(None),
+        };
+
+        match body.content.get("application/json").and_then(|m| m.schema.as_ref()) {
+            Some(schema) => Ok(Some(self.resolve(schema)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The parameters of an endpoint, with where they go, like `path` or `query`.
+    pub fn parameters(&self, endpoint: &Endpoint) -> Result<Vec<(&'static str, Field)>> {
+        let mut parameters = Vec::new();
+        for parameter in &endpoint.op.parameters {
+            let parameter = match parameter {
+                ReferenceOr::Item(parameter) => parameter,
+                ReferenceOr::Reference { .. } => continue,
+            };
+
+            let (location, data) = match parameter {
+                openapiv3::Parameter::Query { parameter_data, .. } => ("query", parameter_data),
+                openapiv3::Parameter::Header { parameter_data, .. } => ("header", parameter_data),
+                openapiv3::Parameter::Path { parameter_data, .. } => ("path", parameter_data),
+                openapiv3::Parameter::Cookie { parameter_data, .. } => ("cookie", parameter_data),
+            };
+
+            let kind = match &data.format {
+                openapiv3::ParameterSchemaOrContent::Schema(schema) => self.kind(self.resolve(schema)?),
+                openapiv3::ParameterSchemaOrContent::Content(_) => "content".to_string(),
+            };
+
+            parameters.push((
+                location,
+                Field {
+                    key: data.name.to_string(),
+                    kind,
+                    required: data.required,
+                    description: first_line(data.description.as_deref().unwrap_or_default()),
+                },
+            ));
+        }
+
+        Ok(parameters)
+    }
+
+    /// The fields of a request body, with keys like `disks[][name]` that can be passed
+    /// to `oxide api -F`.
+    pub fn fields(&self, schema: &Schema) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        self.collect_fields(schema, "", true, 0, &mut fields)?;
+        Ok(fields)
+    }
+
+    fn collect_fields(
+        &self,
+        schema: &Schema,
+        prefix: &str,
+        required: bool,
+        depth: usize,
+        fields: &mut Vec<Field>,
+    ) -> Result<()> {
+        match &schema.schema_kind {
+            SchemaKind::Type(Type::Object(object)) if !object.properties.is_empty() && depth < MAX_DEPTH => {
+                for (name, property) in &object.properties {
+                    let key = if prefix.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{}[{}]", prefix, name)
+                    };
+                    let required = object.required.contains(name);
+                    self.collect_fields(self.resolve(property)?, &key, required, depth + 1, fields)?;
+                }
+            }
+            SchemaKind::Type(Type::Array(array)) if depth < MAX_DEPTH && !prefix.is_empty() => {
+                let items = match &array.items {
+                    Some(items) => self.resolve(items)?,
+                    None => return self.push_field(schema, prefix, required, fields),
+                };
+
+                if self.is_scalar(items) {
+                    self.push_field(schema, prefix, required, fields)?;
+                } else {
+                    self.collect_fields(items, &format!("{}[]", prefix), required, depth + 1, fields)?;
+                }
+            }
+            SchemaKind::AllOf { all_of } if depth < MAX_DEPTH => {
+                for variant in all_of {
+                    let variant = self.resolve(variant)?;
+                    if self.is_scalar(variant) && !prefix.is_empty() {
+                        // A property with a description of its own is wrapped in an allOf.
+                        let mut field = self.field(variant, prefix, required);
+                        if let Some(description) = &schema.schema_data.description {
+                            field.description = first_line(description);
+                        }
+                        merge_field(fields, field);
+                    } else {
+                        self.collect_fields(variant, prefix, required, depth, fields)?;
+                    }
+                }
+            }
+            SchemaKind::OneOf { one_of: variants } | SchemaKind::AnyOf { any_of: variants } if depth < MAX_DEPTH => {
+                let mut variant_fields = Vec::new();
+                for variant in variants {
+                    let mut f = Vec::new();
+                    self.collect_fields(self.resolve(variant)?, prefix, required, depth + 1, &mut f)?;
+                    variant_fields.push(f);
+                }
+
+                // A field is only required if every variant requires it.
+                for f in &variant_fields {
+                    for field in f {
+                        let mut field = field.clone();
+                        field.required = variant_fields
+                            .iter()
+                            .all(|v| v.iter().any(|other| other.key == field.key && other.required));
+                        merge_field(fields, field);
+                    }
+                }
+            }
+            _ => self.push_field(schema, prefix, required, fields)?,
+        }
+
+        Ok(())
+    }
+
+    fn push_field(&self, schema: &Schema, key: &str, required: bool, fields: &mut Vec<Field>) -> Result<()> {
+        if !key.is_empty() {
+            merge_field(fields, self.field(schema, key, required));
+        }
+        Ok(())
+    }
+
+    fn field(&self, schema: &Schema, key: &str, required: bool) -> Field {
+        let data = &schema.schema_data;
+        Field {
+            key: key.to_string(),
+            kind: self.kind(schema),
+            required,
+            description: first_line(
+                data.description
+                    .as_deref()
+                    .or(data.title.as_deref())
+                    .unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Whether a schema is a value rather than an object with fields.
+    fn is_scalar(&self, schema: &Schema) -> bool {
+        match &schema.schema_kind {
+            SchemaKind::Type(Type::Object(_)) | SchemaKind::OneOf { .. } | SchemaKind::AnyOf { .. } => false,
+            SchemaKind::AllOf { all_of } => all_of
+                .iter()
+                .all(|s| self.resolve(s).map(|s| self.is_scalar(s)).unwrap_or(true)),
+            _ => true,
+        }
+    }
+
+    /// The type of a schema as we print it, like `string`, `integer` or `attach|create`.
+    fn kind(&self, schema: &Schema) -> String {
+        match &schema.schema_kind {
+            SchemaKind::Type(Type::String(s)) if !s.enumeration.is_empty() => {
+                s.enumeration.iter().flatten().cloned().collect::<Vec<_>>().join("|")
+            }
+            SchemaKind::Type(Type::String(_)) => "string".to_string(),
+            SchemaKind::Type(Type::Integer(i)) if !i.enumeration.is_empty() => i
+                .enumeration
+                .iter()
+                .flatten()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join("|"),
+            SchemaKind::Type(Type::Integer(_)) => "integer".to_string(),
+            SchemaKind::Type(Type::Number(_)) => "number".to_string(),
+            SchemaKind::Type(Type::Boolean {}) => "boolean".to_string(),
+            SchemaKind::Type(Type::Array(a)) => match a.items.as_ref().map(|items| self.resolve(items)) {
+                Some(Ok(items)) => format!("array of {}", self.kind(items)),
+                _ => "array".to_string(),
+            },
+            SchemaKind::Type(Type::Object(_)) => "object".to_string(),
+            SchemaKind::AllOf { all_of } if all_of.len() == 1 => match self.resolve(&all_of[0]) {
+                Ok(schema) => self.kind(schema),
+                Err(_) => "any".to_string(),
+            },
+            _ => "any".to_string(),
+        }
+    }
+
+    /// Check a request body against the schema of its endpoint, so that mistakes are
+    /// caught before the request is sent. Fields that are not in the schema are left to
+    /// the host.
+    pub fn validate(&self, schema: &Schema, body: &serde_json::Value) -> Result<()> {
+        let mut errors = Vec::new();
+        self.check(schema, body, "", 0, &mut errors)?;
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError(errors).into())
+        }
+    }
+
+    fn check(
+        &self,
+        schema: &Schema,
+        value: &serde_json::Value,
+        path: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) -> Result<()> {
+        if depth > MAX_DEPTH || (value.is_null() && schema.schema_data.nullable) {
+            return Ok(());
+        }
+
+        let name = if path.is_empty() {
+            "the body".to_string()
+        } else {
+            format!("`{}`", path)
+        };
+
+        match &schema.schema_kind {
+            SchemaKind::Type(Type::String(s)) => match value.as_str() {
+                None => errors.push(format!("{} must be a string", name)),
+                Some(v) if !s.enumeration.is_empty() && !s.enumeration.iter().flatten().any(|e| e == v) => {
+                    errors.push(format!("{} must be one of {}", name, self.kind(schema)))
+                }
+                Some(_) => {}
+            },
+            SchemaKind::Type(Type::Integer(i)) => match value.as_i64() {
+                None if value.is_u64() => {}
+                None => errors.push(format!("{} must be an integer", name)),
+                Some(v) if !i.enumeration.is_empty() && !i.enumeration.contains(&Some(v)) => {
+                    errors.push(format!("{} must be one of {}", name, self.kind(schema)))
+                }
+                Some(_) => {}
+            },
+            SchemaKind::Type(Type::Number(_)) if !value.is_number() => {
+                errors.push(format!("{} must be a number", name))
+            }
+            SchemaKind::Type(Type::Boolean {}) if !value.is_boolean() => {
+                errors.push(format!("{} must be a boolean", name))
+            }
+            SchemaKind::Type(Type::Object(object)) => match value.as_object() {
+                None => errors.push(format!("{} must be an object", name)),
+                Some(fields) => {
+                    for required in &object.required {
+                        if !fields.contains_key(required) {
+                            errors.push(format!("`{}` is required", join_path(path, required)));
+                        }
+                    }
+                    for (key, value) in fields {
+                        if let Some(property) = object.properties.get(key) {
+                            self.check(self.resolve(property)?, value, &join_path(path, key), depth + 1, errors)?;
+                        }
+                    }
+                }
+            },
+            SchemaKind::Type(Type::Array(array)) => match value.as_array() {
+                None => errors.push(format!("{} must be an array", name)),
+                Some(values) => {
+                    if let Some(items) = &array.items {
+                        let items = self.resolve(items)?;
+                        for (i, value) in values.iter().enumerate() {
+                            self.check(items, value, &format!("{}[{}]", path, i), depth + 1, errors)?;
+                        }
+                    }
+                }
+            },
+            SchemaKind::AllOf { all_of } => {
+                for schema in all_of {
+                    self.check(self.resolve(schema)?, value, path, depth + 1, errors)?;
+                }
+            }
+            SchemaKind::OneOf { one_of: schemas } | SchemaKind::AnyOf { any_of: schemas } => {
+                // Report the errors of the variant that matches best, which is usually the
+                // one with the `type` that was set.
+                let mut best: Option<Vec<String>> = None;
+                for schema in schemas {
+                    let mut variant_errors = Vec::new();
+                    self.check(self.resolve(schema)?, value, path, depth + 1, &mut variant_errors)?;
+                    if best.as_ref().map(|b| variant_errors.len() < b.len()).unwrap_or(true) {
+                        best = Some(variant_errors);
+                    }
+                }
+                errors.append(&mut best.unwrap_or_default());
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// The schema a reference points to, or the schema itself if it is not a reference.
+    fn resolve<'a, T: Borrow<Schema>>(&'a self, schema: &'a ReferenceOr<T>) -> Result<&'a Schema> {
+        match schema {
+            ReferenceOr::Item(schema) => Ok(schema.borrow()),
+            ReferenceOr::Reference { reference } => {
+                let name = reference.trim_start_matches("#/components/schemas/");
+                let schema = self
+                    .api
+                    .components
+                    .as_ref()
+                    .and_then(|c| c.schemas.get(name))
+                    .ok_or_else(|| anyhow!("schema `{}` is not in the spec", name))?;
+                self.resolve(schema)
+            }
+        }
+    }
+}
+
+/// Add a field, or merge it into a field with the same key from another variant of a
+/// oneOf, like `type` being `attach` in one and `create` in another.
+fn merge_field(fields: &mut Vec<Field>, field: Field) {
+    match fields.iter_mut().find(|f| f.key == field.key) {
+        Some(existing) => {
+            if !existing.kind.split('|').any(|k| k == field.kind) {
+                existing.kind = format!("{}|{}", existing.kind, field.kind);
+            }
+            existing.required = existing.required && field.required;
+            if existing.description.is_empty() {
+                existing.description = field.description;
+            }
+        }
+        None => fields.push(field),
+    }
+}
+
+/// The endpoints of the paths with the most segments that are not parameters.
+fn most_literal(endpoints: Vec<Endpoint<'_>>) -> Vec<Endpoint<'_>> {
+    let most = endpoints.iter().map(|e| e.literal_segments()).max().unwrap_or_default();
+    endpoints.into_iter().filter(|e| e.literal_segments() == most).collect()
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn first_line(s: &str) -> String {
+    s.lines().next().unwrap_or_default().trim().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let spec = Spec::load().unwrap();
+
+        let endpoints = spec.find("organizations_get");
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].method, "GET");
+        assert_eq!(endpoints[0].path, "/organizations");
+        assert_eq!(endpoints[0].tag(), "organizations");
+
+        let methods: Vec<&str> = spec
+            .find("/organizations/{organization_name}")
+            .iter()
+            .map(|e| e.method)
+            .collect();
+        assert_eq!(methods, vec!["GET", "PUT", "DELETE"]);
+
+        let endpoint = spec
+            .endpoint("post", "organizations/maze-war/projects?limit=10")
+            .unwrap();
+        assert_eq!(endpoint.id(), "organization_projects_post");
+
+        assert!(spec.find("/organizations/maze-war/teapots").is_empty());
+        assert!(spec.endpoint("PATCH", "/organizations").is_none());
+    }
+
+    #[test]
+    fn test_fields() {
+        let spec = Spec::load().unwrap();
+        let endpoint = spec
+            .endpoint("POST", "/organizations/maze-war/projects/prod-online/instances")
+            .unwrap();
+        let schema = spec.body_schema(&endpoint).unwrap().unwrap();
+        let fields = spec.fields(schema).unwrap();
+
+        let field = |key: &str| fields.iter().find(|f| f.key == key).unwrap().clone();
+        assert_eq!(field("name").kind, "string");
+        assert!(field("name").required);
+        assert_eq!(field("ncpus").kind, "integer");
+        assert_eq!(field("disks[][type]").kind, "create|attach");
+        assert!(field("disks[][name]").required);
+        assert!(!field("disks[][size]").required);
+        assert_eq!(field("network_interfaces[type]").kind, "create|default|none");
+        assert!(!field("user_data").description.is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let spec = Spec::load().unwrap();
+        let endpoint = spec
+            .endpoint("POST", "/organizations/maze-war/projects/prod-online/instances")
+            .unwrap();
+        let schema = spec.body_schema(&endpoint).unwrap().unwrap();
+
+        let valid = serde_json::json!({
+            "name": "db1",
+            "description": "a database",
+            "hostname": "db1",
+            "ncpus": 2,
+            "memory": 1073741824,
+            "disks": [{"type": "attach", "name": "disk1"}],
+            "network_interfaces": {"type": "default"},
+            "unknown": true,
+        });
+        spec.validate(schema, &valid).unwrap();
+
+        let invalid = serde_json::json!({
+            "name": "db1",
+            "description": "a database",
+            "ncpus": "two",
+            "memory": 1073741824,
+            "disks": [{"type": "attach"}, {"type": "mount", "name": "disk2"}],
+        });
+        let err = spec.validate(schema, &invalid).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ValidationError>().unwrap().0,
+            vec![
+                "`hostname` is required",
+                "`disks[0].name` is required",
+                "`disks[1].type` must be one of attach",
+                "`ncpus` must be an integer",
+            ]
+        );
+
+        let err = spec.validate(schema, &serde_json::json!([])).unwrap_err();
+        assert_eq!(err.to_string(), "invalid request body: the body must be an object");
+    }
+}
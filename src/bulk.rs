@@ -0,0 +1,157 @@
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Result};
+use futures::StreamExt;
+
+/// Delete resources by their names, `parallel` at a time.
+///
+/// Unless `confirm` is set, the user confirms all of them at once first: by typing the
+/// name for one resource, or how many there are after a list of them for more. The
+/// result for each resource is printed as it is deleted, with `describe` saying what it
+/// is, like `disk foo from maze-war/prod-online`.
+///
+/// When only one resource is deleted, its error is returned as it is, so it keeps its
+/// exit code.
+pub async fn delete<F, Fut>(
+    ctx: &mut crate::context::Context<'_>,
+    plural: &str,
+    names: Vec<String>,
+    describe: impl Fn(&str) -> String,
+    confirm: bool,
+    parallel: usize,
+    delete: F,
+) -> Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    if names.is_empty() {
+        writeln!(ctx.io.out, "No {} to delete", plural)?;
+        return Ok(());
+    }
+
+    // Confirm deletion.
+    if !confirm {
+        let want = if names.len() == 1 {
+            names[0].to_string()
+        } else {
+            writeln!(ctx.io.out, "About to delete {} {}:", names.len(), plural)?;
+            for name in &names {
+                writeln!(ctx.io.out, "  {}", describe(name))?;
+            }
+            names.len().to_string()
+        };
+
+        let confirmation = dialoguer::Input::<String>::new()
+            .with_prompt(format!("Type {} to confirm deletion:", want))
+            .validate_with(|input: &String| -> Result<(), &str> {
+                if input.trim() == want {
+                    Ok(())
+                } else {
+                    Err("mismatched confirmation")
+                }
+            })
+            .interact_text();
+        if let Err(err) = confirmation {
+            return Err(anyhow!("prompt failed: {}", err));
+        }
+    }
+
+    let total = names.len();
+    let mut results = futures::stream::iter(names)
+        .map(|name| {
+            let deleted = delete(name.to_string());
+            async move { (name, deleted.await) }
+        })
+        .buffered(parallel.max(1));
+
+    let cs = ctx.io.color_scheme();
+    let mut failed = 0;
+    while let Some((name, result)) = results.next().await {
+        match result {
+            Ok(()) => writeln!(
+                ctx.io.out,
+                "{} Deleted {}",
+                cs.success_icon_with_color(ansi_term::Color::Red),
+                describe(&name)
+            )?,
+            Err(err) if total == 1 => return Err(err),
+            Err(err) => {
+                failed += 1;
+                writeln!(
+                    ctx.io.err_out,
+                    "{} Failed to delete {}: {}",
+                    cs.failure_icon(),
+                    describe(&name),
+                    ctx.io.gateway().resolve(err)
+                )?;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!("failed to delete {} of {} {}", failed, total, plural);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_delete() {
+        let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+        io.set_color_enabled(false);
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        let names = vec!["disk-1".to_string(), "disk-2".to_string(), "disk-3".to_string()];
+        let err = super::delete(
+            &mut ctx,
+            "disks",
+            names,
+            |name| format!("disk {}", name),
+            true,
+            2,
+            |name| async move {
+                if name == "disk-2" {
+                    anyhow::bail!("not found: disk with name \"{}\"", name);
+                }
+                Ok(())
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "failed to delete 1 of 3 disks");
+
+        assert_eq!(
+            std::fs::read_to_string(&stdout_path).unwrap(),
+            "✔ Deleted disk disk-1\n✔ Deleted disk disk-3\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&stderr_path).unwrap(),
+            "✘ Failed to delete disk disk-2: not found: disk with name \"disk-2\"\n"
+        );
+
+        // The error of a single resource is returned as it is.
+        let err = super::delete(
+            &mut ctx,
+            "disks",
+            vec!["disk-4".to_string()],
+            |name| format!("disk {}", name),
+            true,
+            2,
+            |_| async { Err(anyhow::anyhow!("not found")) },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "not found");
+    }
+}
@@ -25,7 +25,26 @@ use serde::{Deserialize, Serialize};
 /// - literal values "true", "false", "null", and integer/float numbers get converted to
 ///   appropriate JSON types;
 /// - if the value starts with "@", the rest of the value is interpreted as a
-///   filename to read the value from. Pass "-" to read from standard input.
+///   filename to read the value from. Pass "-" to read from standard input. Files
+///   ending in ".json" are embedded as JSON rather than as a string.
+///
+/// Both `-f` and `-F` build nested values from their keys. Use `params.name` or
+/// `params[name]` for a field of an object, and `disks[]` to add to an array. Fields
+/// after `[]` go in the last element of the array, unless they are already set there,
+/// in which case they start a new element:
+///
+///     $ oxide api /organizations/maze-war/projects/prod-online/instances \
+///         -f name=db1 -f hostname=db1 -f description=db -F ncpus=2 -F memory=1073741824 \
+///         -f 'disks[][type]=attach' -f 'disks[][name]=disk1' \
+///         -f 'network_interfaces.type=default'
+///
+/// The fields are checked against the API spec that oxide was built with before they
+/// are sent. List the endpoints of the API with `--list`, and see the parameters and
+/// fields of one with `oxide api describe`:
+///
+///     $ oxide api --list
+///
+///     $ oxide api describe instance_create
 ///
 /// Raw request body may be passed from the outside via a file specified by `--input`.
 /// Pass "-" to read from standard input. In this mode, parameters specified via
@@ -33,19 +52,48 @@ use serde::{Deserialize, Serialize};
 ///
 /// In `--paginate` mode, all pages of results will sequentially be requested until
 /// there are no more pages of results.
+///
+/// Requests that could not connect are retried after a delay. Idempotent requests, like
+/// GET, PUT and DELETE, are also retried if they got a 503 or a 429, honoring the
+/// `Retry-After` of the response, or if the connection fails midway. Set how many times
+/// with the global `--retries` flag.
+///
+/// Use the global `--query` (or `--jq`) flag to pick values out of the response with
+/// a jq expression. Strings, numbers, and booleans are printed without quotes. Or use
+/// `--template` to format the response with a Go template. Responses that are not JSON
+/// are printed as they are.
+///
+///     $ oxide api /organizations --paginate --jq '.[].name'
+///
+///     $ oxide api /organizations --paginate --template '{{range .}}{{tablerow .name .id}}{{end}}'
+///
+/// If the request fails, the error the server responded with is printed and oxide
+/// exits with a non-zero code. Use `--silent` to print nothing of the response, and
+/// `--verbose` to print the request and the response headers to standard error.
 #[derive(Parser, Debug, Clone)]
-#[clap(verbatim_doc_comment)]
+#[clap(
+    verbatim_doc_comment,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 pub struct CmdApi {
+    #[clap(subcommand)]
+    subcmd: Option<SubCommand>,
+
     /// The endpoint to request.
-    #[clap(name = "endpoint", required = true)]
-    pub endpoint: String,
+    #[clap(name = "endpoint", required_unless_present = "list")]
+    pub endpoint: Option<String>,
+
+    /// List the endpoints of the API, grouped by tag.
+    #[clap(long, conflicts_with = "endpoint")]
+    pub list: bool,
 
     /// The HTTP method for the request.
     #[clap(short = 'X', long)]
     pub method: Option<http::method::Method>,
 
     /// Make additional HTTP requests to fetch all pages of results.
-    #[clap(long, conflicts_with = "input")]
+    #[clap(long)]
     pub paginate: bool,
 
     /// Add a typed parameter in key=value format.
@@ -56,14 +104,27 @@ pub struct CmdApi {
     #[clap(short = 'f', long)]
     pub raw_field: Vec<String>,
 
+    /// The `--field` and `--raw-field` flags in the order they were given, each with
+    /// whether it is typed, see `set_field_order`.
+    #[clap(skip)]
+    pub field_order: Vec<(bool, String)>,
+
     /// The file to use as body for the HTTP request (use "-" to read from standard input).
-    #[clap(long, default_value = "", conflicts_with = "paginate")]
+    #[clap(long, default_value = "")]
     pub input: String,
 
     /// Include HTTP response headers in the output.
     #[clap(short, long)]
     pub include: bool,
 
+    /// Do not print the response body.
+    #[clap(long)]
+    pub silent: bool,
+
+    /// Print the request and the response headers to standard error.
+    #[clap(long)]
+    pub verbose: bool,
+
     /// Add a HTTP request header in `key:value` format.
     #[clap(short = 'H', long)]
     pub header: Vec<String>,
@@ -78,15 +139,28 @@ pub struct PaginatableResponse {
     pub next_page: Option<String>,
 }
 
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    Describe(CmdApiDescribe),
+}
+
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdApi {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if let Some(SubCommand::Describe(cmd)) = &self.subcmd {
+            return cmd.run(ctx).await;
+        }
+
+        if self.list {
+            return list_endpoints(ctx);
+        }
+
         // Let's get the api client.
-        let client = ctx.api_client("")?;
+        let client = ctx.api_http_client("").await?;
 
         // Make sure the endpoint starts with a slash.
-        let mut endpoint = self.endpoint.to_string();
-        if !self.endpoint.starts_with('/') {
+        let mut endpoint = self.endpoint.clone().unwrap_or_default();
+        if !endpoint.starts_with('/') {
             endpoint = format!("/{}", endpoint);
         }
 
@@ -115,6 +189,17 @@ impl crate::cmd::Command for CmdApi {
             return Err(anyhow!("the `--paginate` option is not supported for non-GET requests",));
         }
 
+        // Catch mistakes in the fields before sending them. Endpoints that are not in
+        // the spec, maybe because the host is newer, are sent as they are.
+        if self.input.is_empty() && !params.is_empty() {
+            let spec = crate::api_spec::Spec::load()?;
+            if let Some(api_endpoint) = spec.endpoint(method.as_str(), &endpoint) {
+                if let Some(schema) = spec.body_schema(&api_endpoint)? {
+                    spec.validate(schema, &serde_json::Value::Object(params.clone()))?;
+                }
+            }
+        }
+
         // Parse the input file.
         if !self.input.is_empty() {
             // Read the input file.
@@ -145,28 +230,37 @@ impl crate::cmd::Command for CmdApi {
             }
         }
 
-        // Make the request.
+        let headers = self.parse_headers()?;
+
         let mut has_next_page = true;
-        let mut result = serde_json::Value::Null;
         let mut page_results: Vec<serde_json::Value> = Vec::new();
         while has_next_page {
-            let body = if bytes.is_empty() {
-                None
-            } else {
-                Some(reqwest::Body::from(bytes.clone()))
-            };
-
-            let mut req = client.request_raw(method.clone(), &endpoint, body).await?;
+            let mut req = client.request(method.clone(), &endpoint);
 
             // Let's add our headers.
-            let headers = self.parse_headers()?;
-            if !headers.is_empty() {
-                for (key, value) in headers {
-                    req = req.header(key, value);
-                }
+            for (key, value) in &headers {
+                req = req.header(key, value);
+            }
+
+            if !bytes.is_empty() {
+                req = req.body(bytes.clone());
+            }
+
+            let req = req.build()?;
+            if self.verbose {
+                print_request(ctx, &req, &bytes)?;
             }
 
-            let resp = req.send().await?;
+            // Make the request, retrying it if it fails for a passing reason.
+            let resp = client.send(req).await?;
+
+            if self.verbose {
+                writeln!(ctx.io.err_out, "< {:?} {}", resp.version(), resp.status())?;
+                for (name, value) in crate::trace::redact_headers(resp.headers()) {
+                    writeln!(ctx.io.err_out, "< {}: {}", name, value)?;
+                }
+                writeln!(ctx.io.err_out, "<")?;
+            }
 
             // Print the response headers if requested.
             if self.include {
@@ -174,20 +268,20 @@ impl crate::cmd::Command for CmdApi {
                 print_headers(ctx, resp.headers())?;
             }
 
-            if resp.status() == 204 {
-                return Ok(());
-            }
+            let status = resp.status();
+            let resp_headers = resp.headers().clone();
+            let body = resp.bytes().await?;
 
-            if !resp.status().is_success() {
-                return Err(anyhow!(
-                    "{} {}",
-                    resp.status(),
-                    resp.status().canonical_reason().unwrap_or("")
-                ));
+            if !status.is_success() {
+                // Print the error of the server, it says more than the status.
+                if !self.silent {
+                    write_body(ctx, &resp_headers, &body, false)?;
+                }
+                return Err(crate::errors::HttpError::from_body(status, &resp_headers, &body).into());
             }
 
             if self.paginate {
-                let mut page: PaginatableResponse = resp.json().await?;
+                let mut page: PaginatableResponse = serde_json::from_slice(&body)?;
 
                 if !page.items.is_empty() {
                     page_results.append(&mut page.items);
@@ -202,23 +296,84 @@ impl crate::cmd::Command for CmdApi {
                     }
                 }
             } else {
-                // Read the response body.
-                result = resp.json().await?;
+                if !self.silent {
+                    write_body(ctx, &resp_headers, &body, true)?;
+                }
                 has_next_page = false;
             }
         }
 
-        if self.paginate {
-            result = serde_json::Value::Array(page_results);
+        if self.paginate && !self.silent {
+            ctx.io.write_output_json(&serde_json::Value::Array(page_results))?;
         }
 
-        ctx.io.write_output_json(&result)?;
-
         Ok(())
     }
 }
 
+/// Print a request with `--verbose`, like `> GET https://oxide.example.com/organizations`
+/// followed by its headers and body.
+fn print_request(ctx: &mut crate::context::Context, req: &reqwest::Request, body: &[u8]) -> Result<()> {
+    writeln!(ctx.io.err_out, "> {} {}", req.method(), req.url())?;
+    for (name, value) in crate::trace::redact_headers(req.headers()) {
+        writeln!(ctx.io.err_out, "> {}: {}", name, value)?;
+    }
+    writeln!(ctx.io.err_out, ">")?;
+    if !body.is_empty() {
+        writeln!(ctx.io.err_out, "{}", String::from_utf8_lossy(body))?;
+    }
+
+    Ok(())
+}
+
+/// Print a response body. JSON is formatted, and filtered with `--query` or `--template`
+/// if `filter` is true. Any other content is printed as is.
+fn write_body(
+    ctx: &mut crate::context::Context,
+    headers: &reqwest::header::HeaderMap,
+    body: &[u8],
+    filter: bool,
+) -> Result<()> {
+    if body.is_empty() {
+        return Ok(());
+    }
+
+    let is_json = headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|content_type| content_type.contains("json"))
+        .unwrap_or_default();
+
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(json) if is_json && filter => ctx.io.write_output_json(&json)?,
+        Ok(json) if is_json => writeln!(ctx.io.out, "{}", serde_json::to_string_pretty(&json)?)?,
+        _ => ctx.io.out.write_all(body)?,
+    }
+
+    Ok(())
+}
+
 impl CmdApi {
+    /// Keep the order of the `--field` and `--raw-field` flags, which clap parses
+    /// apart, so fields are set in the order they were given, like the keys of the
+    /// items of an array: `-f 'disks[][name]=a' -F 'disks[][size]=1' -f 'disks[][name]=b'`.
+    pub fn set_field_order(&mut self, matches: &clap::ArgMatches) {
+        let with_indices = |id: &str, values: &[String], typed: bool| {
+            matches
+                .indices_of(id)
+                .into_iter()
+                .flatten()
+                .zip(values.iter().map(move |value| (typed, value.to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let mut fields = with_indices("raw-field", &self.raw_field, false);
+        fields.extend(with_indices("field", &self.field, true));
+        fields.sort_by_key(|(index, _)| *index);
+
+        self.field_order = fields.into_iter().map(|(_, field)| field).collect();
+    }
+
     fn parse_headers(&self) -> Result<HashMap<String, String>> {
         let mut headers: HashMap<String, String> = HashMap::new();
 
@@ -233,35 +388,48 @@ impl CmdApi {
         Ok(headers)
     }
 
-    fn parse_fields(&self, ctx: &mut crate::context::Context) -> Result<HashMap<String, serde_json::Value>> {
-        let mut params: HashMap<String, serde_json::Value> = HashMap::new();
+    fn parse_fields(&self, ctx: &mut crate::context::Context) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let mut params = serde_json::Map::new();
+
+        // The order is only known from the command line, see `set_field_order`. A command
+        // made in code sets the raw fields first, and then the typed ones.
+        let fields = if self.field_order.is_empty() {
+            self.raw_field
+                .iter()
+                .map(|field| (false, field.to_string()))
+                .chain(self.field.iter().map(|field| (true, field.to_string())))
+                .collect()
+        } else {
+            self.field_order.clone()
+        };
 
-        // Parse the raw fields.
-        // These are always added as strings.
-        for f in self.raw_field.iter() {
-            let mut parts = f.splitn(2, '=');
-            let key = parts.next().ok_or_else(|| anyhow!("missing key in --raw-field"))?;
-            let value = parts.next().ok_or_else(|| anyhow!("missing value in --raw-field"))?;
+        for (typed, t) in fields.iter() {
+            // Parse the raw fields.
+            // These are always added as strings.
+            if !typed {
+                let mut parts = t.splitn(2, '=');
+                let key = parts.next().ok_or_else(|| anyhow!("missing key in --raw-field"))?;
+                let value = parts.next().ok_or_else(|| anyhow!("missing value in --raw-field"))?;
 
-            params.insert(key.to_string(), serde_json::Value::String(value.to_string()));
-        }
+                set_field(&mut params, key, serde_json::Value::String(value.to_string()))?;
+                continue;
+            }
 
-        // Parse the typed fields.
-        for t in self.field.iter() {
+            // Parse the typed fields.
             let mut parts = t.splitn(2, '=');
             let key = parts.next().ok_or_else(|| anyhow!("missing key in --field"))?;
             let value = parts.next().ok_or_else(|| anyhow!("missing value in --field"))?;
 
             // See if value parses as an integer.
             if let Ok(i) = value.parse::<i64>() {
-                params.insert(key.to_string(), serde_json::Value::Number(i.into()));
+                set_field(&mut params, key, serde_json::Value::Number(i.into()))?;
                 continue;
             }
 
             // See if value parses as a float.
             if let Ok(f) = value.parse::<f64>() {
                 let num = serde_json::Number::from_f64(f).ok_or_else(|| anyhow!("invalid float {}", f))?;
-                params.insert(key.to_string(), serde_json::Value::Number(num));
+                set_field(&mut params, key, serde_json::Value::Number(num))?;
                 continue;
             }
 
@@ -277,7 +445,14 @@ impl CmdApi {
                         let mut file = std::fs::File::open(filename)?;
                         let mut contents = String::new();
                         file.read_to_string(&mut contents)?;
-                        serde_json::Value::String(contents)
+
+                        // JSON files are embedded as JSON rather than as a string.
+                        if filename.ends_with(".json") {
+                            serde_json::from_str(&contents)
+                                .map_err(|err| anyhow!("invalid JSON in `{}`: {}", filename, err))?
+                        } else {
+                            serde_json::Value::String(contents)
+                        }
                     } else if value == "-" {
                         // Read from stdin.
                         let mut contents = String::new();
@@ -289,13 +464,235 @@ impl CmdApi {
                 }
             };
 
-            params.insert(key.to_string(), value);
+            set_field(&mut params, key, value)?;
         }
 
         Ok(params)
     }
 }
 
+/// A part of the key of a field, like `disks`, `[]`, `[type]` or `.type`.
+#[derive(Debug, Clone, PartialEq)]
+enum KeyPart {
+    Key(String),
+    Append,
+}
+
+/// Parse the key of a field, like `disks[][type]` or `network_interfaces.params.name`.
+fn parse_key(key: &str) -> Result<Vec<KeyPart>> {
+    let invalid = || {
+        anyhow!(
+            "invalid key `{}`, for example use `name`, `disks[][name]` or `params.name`",
+            key
+        )
+    };
+
+    let first = key.find(['[', '.']).unwrap_or(key.len());
+    if first == 0 {
+        return Err(invalid());
+    }
+
+    let mut parts = vec![KeyPart::Key(key[..first].to_string())];
+    let mut rest = &key[first..];
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("[]") {
+            parts.push(KeyPart::Append);
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = r.find(']').ok_or_else(invalid)?;
+            if end == 0 || r[..end].contains('[') {
+                return Err(invalid());
+            }
+            parts.push(KeyPart::Key(r[..end].to_string()));
+            rest = &r[end + 1..];
+        } else if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['[', '.']).unwrap_or(r.len());
+            if end == 0 {
+                return Err(invalid());
+            }
+            parts.push(KeyPart::Key(r[..end].to_string()));
+            rest = &r[end..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(parts)
+}
+
+/// Set a field in the parameters by its key, creating the objects and arrays on its
+/// path. Like with gh, `[]` appends a new element to an array, unless the field can be
+/// set in the last element without overwriting anything. So `-F 'disks[][type]=attach'
+/// -F 'disks[][name]=foo'` makes one disk, and another `-F 'disks[][type]=...'` starts
+/// the next one.
+fn set_field(
+    params: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<()> {
+    let parts = parse_key(key)?;
+
+    // Keys always start with a name, see `parse_key`.
+    let (name, rest) = match parts.split_first() {
+        Some((KeyPart::Key(name), rest)) => (name, rest),
+        _ => unreachable!(),
+    };
+
+    set_path(params.entry(name).or_insert(serde_json::Value::Null), rest, value)
+        .map_err(|err| anyhow!("cannot set `{}`: {}", key, err))
+}
+
+fn set_path(target: &mut serde_json::Value, parts: &[KeyPart], value: serde_json::Value) -> Result<()> {
+    let (part, rest) = match parts.split_first() {
+        Some(split) => split,
+        None => {
+            *target = value;
+            return Ok(());
+        }
+    };
+
+    match part {
+        KeyPart::Key(key) => {
+            if target.is_null() {
+                *target = serde_json::Value::Object(Default::default());
+            }
+            let object = target
+                .as_object_mut()
+                .ok_or_else(|| anyhow!("`{}` is not in an object", key))?;
+            set_path(object.entry(key).or_insert(serde_json::Value::Null), rest, value)
+        }
+        KeyPart::Append => {
+            if target.is_null() {
+                *target = serde_json::Value::Array(Default::default());
+            }
+            let array = target
+                .as_array_mut()
+                .ok_or_else(|| anyhow!("`[]` is not on an array"))?;
+            let reuse_last = !rest.is_empty() && array.last().map(|last| !has_path(last, rest)).unwrap_or_default();
+            if !reuse_last {
+                array.push(serde_json::Value::Null);
+            }
+            set_path(array.last_mut().unwrap(), rest, value)
+        }
+    }
+}
+
+/// Whether setting the path would overwrite a value that is already set.
+fn has_path(target: &serde_json::Value, parts: &[KeyPart]) -> bool {
+    match parts.split_first() {
+        None => true,
+        Some((KeyPart::Key(key), rest)) => target.get(key).map(|v| has_path(v, rest)).unwrap_or_default(),
+        Some((KeyPart::Append, _)) => false,
+    }
+}
+
+/// Print the endpoints of the API, grouped by tag.
+fn list_endpoints(ctx: &mut crate::context::Context) -> Result<()> {
+    let spec = crate::api_spec::Spec::load()?;
+    let mut endpoints: Vec<crate::api_spec::Endpoint> =
+        spec.endpoints().into_iter().filter(|e| e.tag() != "hidden").collect();
+    endpoints.sort_by_key(|e| e.tag().to_string());
+
+    let cs = ctx.io.color_scheme();
+    let mut tw = tabwriter::TabWriter::new(vec![]);
+    let mut tag = None;
+    for endpoint in endpoints {
+        if tag != Some(endpoint.tag()) {
+            if tag.is_some() {
+                writeln!(tw)?;
+            }
+            writeln!(tw, "{}", cs.bold(endpoint.tag()))?;
+            tag = Some(endpoint.tag());
+        }
+
+        writeln!(tw, "  {}\t{}\t{}", endpoint.method, endpoint.path, endpoint.id())?;
+    }
+    tw.flush()?;
+
+    let table = String::from_utf8(tw.into_inner()?)?;
+    write!(ctx.io.out, "{}", table)?;
+
+    Ok(())
+}
+
+/// Describe an endpoint of the API.
+///
+/// Prints the parameters of the endpoint and the fields of its request body, with their
+/// types and whether they are required. The fields are named the way they are set with
+/// `oxide api -F`.
+///
+///     $ oxide api describe instance_create
+///
+///     $ oxide api describe /organizations/maze-war/projects
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdApiDescribe {
+    /// The operation ID or the path of the endpoint.
+    #[clap(name = "endpoint", required = true)]
+    pub endpoint: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdApiDescribe {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let spec = crate::api_spec::Spec::load()?;
+        let endpoints = spec.find(&self.endpoint);
+        if endpoints.is_empty() {
+            return Err(anyhow!(
+                "no endpoint `{}` in the API, see `oxide api --list`",
+                self.endpoint
+            ));
+        }
+
+        let cs = ctx.io.color_scheme();
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            if i > 0 {
+                writeln!(ctx.io.out)?;
+            }
+
+            writeln!(
+                ctx.io.out,
+                "{}",
+                cs.bold(&format!("{} {}", endpoint.method, endpoint.path))
+            )?;
+            let summary = endpoint.op.summary.as_deref().unwrap_or_default();
+            writeln!(ctx.io.out, "{}: {}", endpoint.id(), summary)?;
+
+            let mut tw = tabwriter::TabWriter::new(vec![]);
+            let parameters = spec.parameters(endpoint)?;
+            if !parameters.is_empty() {
+                writeln!(tw, "\n{}", cs.bold("Parameters:"))?;
+                for (location, field) in parameters {
+                    let required = if field.required { "required" } else { "" };
+                    writeln!(
+                        tw,
+                        "  {}\t{}\t{}\t{}\t{}",
+                        field.key, location, field.kind, required, field.description
+                    )?;
+                }
+            }
+
+            if let Some(schema) = spec.body_schema(endpoint)? {
+                writeln!(tw, "\n{}", cs.bold("Body fields:"))?;
+                for field in spec.fields(schema)? {
+                    let required = if field.required { "required" } else { "" };
+                    writeln!(
+                        tw,
+                        "  {}\t{}\t{}\t{}",
+                        field.key, field.kind, required, field.description
+                    )?;
+                }
+            }
+            tw.flush()?;
+
+            let table = String::from_utf8(tw.into_inner()?)?;
+            write!(ctx.io.out, "{}", table)?;
+        }
+
+        Ok(())
+    }
+}
+
 fn print_headers(ctx: &mut crate::context::Context, headers: &reqwest::header::HeaderMap) -> Result<()> {
     let mut names: Vec<String> = headers.keys().map(|k| k.as_str().to_string()).collect();
     names.sort_by_key(|a| a.to_lowercase());
@@ -351,4 +748,119 @@ mod test {
         expected = "https://api.github.com/users/octocat/repos?page=2&per_page=100&foo=bar";
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("name").unwrap(), vec![KeyPart::Key("name".to_string())]);
+        assert_eq!(
+            parse_key("disks[][type]").unwrap(),
+            vec![
+                KeyPart::Key("disks".to_string()),
+                KeyPart::Append,
+                KeyPart::Key("type".to_string())
+            ]
+        );
+        assert_eq!(
+            parse_key("network_interfaces.params[0].name").unwrap(),
+            vec![
+                KeyPart::Key("network_interfaces".to_string()),
+                KeyPart::Key("params".to_string()),
+                KeyPart::Key("0".to_string()),
+                KeyPart::Key("name".to_string())
+            ]
+        );
+
+        for key in [
+            "",
+            "[]",
+            ".name",
+            "disks[",
+            "disks[]x",
+            "disks[[a]]",
+            "params..name",
+            "params.",
+        ] {
+            assert!(parse_key(key).is_err(), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_set_field() {
+        let mut params = serde_json::Map::new();
+        let fields = vec![
+            ("name", serde_json::json!("db1")),
+            ("ncpus", serde_json::json!(2)),
+            ("disks[][type]", serde_json::json!("attach")),
+            ("disks[][name]", serde_json::json!("foo")),
+            ("disks[][type]", serde_json::json!("attach")),
+            ("disks[][name]", serde_json::json!("bar")),
+            ("network_interfaces.type", serde_json::json!("default")),
+            ("tags[]", serde_json::json!("a")),
+            ("tags[]", serde_json::json!("b")),
+            ("user_data", serde_json::json!({"packages": ["vim"]})),
+        ];
+        for (key, value) in fields {
+            set_field(&mut params, key, value).unwrap();
+        }
+
+        assert_eq!(
+            serde_json::Value::Object(params.clone()),
+            serde_json::json!({
+                "name": "db1",
+                "ncpus": 2,
+                "disks": [{"type": "attach", "name": "foo"}, {"type": "attach", "name": "bar"}],
+                "network_interfaces": {"type": "default"},
+                "tags": ["a", "b"],
+                "user_data": {"packages": ["vim"]},
+            })
+        );
+
+        let err = set_field(&mut params, "name.first", serde_json::json!("x")).unwrap_err();
+        assert_eq!(err.to_string(), "cannot set `name.first`: `first` is not in an object");
+
+        let err = set_field(&mut params, "ncpus[]", serde_json::json!(1)).unwrap_err();
+        assert_eq!(err.to_string(), "cannot set `ncpus[]`: `[]` is not on an array");
+
+        // Fields are set in the order they were given, whether they are typed or not.
+        use clap::{CommandFactory, FromArgMatches};
+        let matches = CmdApi::command().get_matches_from([
+            "api",
+            "/organizations/maze-war/projects/prod-online/disks",
+            "-f",
+            "disks[][name]=a",
+            "-F",
+            "disks[][size]=1",
+            "-f",
+            "disks[][name]=b",
+            "-F",
+            "disks[][size]=2",
+        ]);
+        let mut cmd = CmdApi::from_arg_matches(&matches).unwrap();
+        cmd.set_field_order(&matches);
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, _, _) = crate::iostreams::IoStreams::test();
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        assert_eq!(
+            serde_json::Value::Object(cmd.parse_fields(&mut ctx).unwrap()),
+            serde_json::json!({
+                "disks": [{"name": "a", "size": 1}, {"name": "b", "size": 2}],
+            })
+        );
+
+        // Without the order, the raw fields are set first and then the typed ones.
+        cmd.field_order.clear();
+        cmd.raw_field = vec!["name=maze-war".to_string()];
+        cmd.field = vec!["ncpus=2".to_string()];
+        assert_eq!(
+            serde_json::Value::Object(cmd.parse_fields(&mut ctx).unwrap()),
+            serde_json::json!({"name": "maze-war", "ncpus": 2})
+        );
+    }
 }
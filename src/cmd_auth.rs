@@ -2,10 +2,11 @@ use std::collections::HashMap;
 
 use anyhow::{anyhow, Result};
 use clap::Parser;
-use oauth2::basic::BasicClient;
-use oauth2::devicecode::StandardDeviceAuthorizationResponse;
-use oauth2::reqwest::async_http_client;
-use oauth2::{AuthType, AuthUrl, ClientId, DeviceAuthorizationUrl, TokenResponse, TokenUrl};
+use oauth2::{
+    basic::BasicClient, devicecode::StandardDeviceAuthorizationResponse, AuthType, AuthUrl, ClientId,
+    DeviceAuthorizationUrl, HttpRequest, HttpResponse, TokenResponse, TokenUrl,
+};
+use tabled::Tabled;
 
 /// Login, logout, and get the status of your authentication.
 ///
@@ -21,7 +22,10 @@ pub struct CmdAuth {
 enum SubCommand {
     Login(CmdAuthLogin),
     Logout(CmdAuthLogout),
+    Refresh(CmdAuthRefresh),
     Status(CmdAuthStatus),
+    Switch(CmdAuthSwitch),
+    Token(CmdAuthToken),
 }
 
 #[async_trait::async_trait]
@@ -30,7 +34,10 @@ impl crate::cmd::Command for CmdAuth {
         match &self.subcmd {
             SubCommand::Login(cmd) => cmd.run(ctx).await,
             SubCommand::Logout(cmd) => cmd.run(ctx).await,
+            SubCommand::Refresh(cmd) => cmd.run(ctx).await,
             SubCommand::Status(cmd) => cmd.run(ctx).await,
+            SubCommand::Switch(cmd) => cmd.run(ctx).await,
+            SubCommand::Token(cmd) => cmd.run(ctx).await,
         }
     }
 }
@@ -77,6 +84,64 @@ pub fn parse_host(input: &str) -> Result<url::Url> {
     }
 }
 
+/// The OAuth 2.0 client to get tokens for a host with.
+pub fn oauth_client(ctx: &crate::context::Context, host: &str) -> Result<BasicClient> {
+    let device_auth_url = DeviceAuthorizationUrl::new(format!("{}device/auth", host))?;
+    let client_id = ctx.config.get("", "client_id")?;
+
+    Ok(BasicClient::new(
+        ClientId::new(client_id),
+        None,
+        AuthUrl::new(format!("{}authorize", host))?,
+        Some(TokenUrl::new(format!("{}device/token", host))?),
+    )
+    .set_auth_type(AuthType::RequestBody)
+    .set_device_authorization_url(device_auth_url))
+}
+
+/// Send an OAuth 2.0 request with an HTTP client that has the TLS settings of the host.
+/// This is `oauth2::reqwest::async_http_client`, with our own client.
+pub async fn oauth_request(
+    http: reqwest::Client,
+    request: HttpRequest,
+) -> std::result::Result<HttpResponse, oauth2::reqwest::Error<reqwest::Error>> {
+    let mut request_builder = http.request(request.method, request.url.as_str()).body(request.body);
+    for (name, value) in &request.headers {
+        request_builder = request_builder.header(name.as_str(), value.as_bytes());
+    }
+
+    let response = request_builder.send().await.map_err(oauth2::reqwest::Error::Reqwest)?;
+
+    let status_code = response.status();
+    let headers = response.headers().to_owned();
+    let body = response.bytes().await.map_err(oauth2::reqwest::Error::Reqwest)?;
+
+    Ok(HttpResponse {
+        status_code,
+        headers,
+        body: body.to_vec(),
+    })
+}
+
+/// Log in to an instance that allows spoofed tokens, like a local omicron for
+/// development, as a user by its ID, and return the token.
+///
+/// Requests are authenticated with the spoofed token itself. Logging in checks that
+/// the instance accepts it and that the user exists.
+async fn spoof_login(ctx: &crate::context::Context<'_>, host: &str, user_id: &str) -> Result<String> {
+    let token = format!("oxide-spoof-{}", user_id);
+
+    ctx.api_client_with_token(host, &token)?
+        .hidden()
+        .spoof_login(&oxide_api::types::SpoofLoginBody {
+            username: user_id.to_string(),
+        })
+        .await
+        .map_err(|err| anyhow!("could not log in to {} as {}: {}", host, user_id, err))?;
+
+    Ok(token)
+}
+
 fn parse_host_interactively(ctx: &mut crate::context::Context) -> Result<url::Url> {
     loop {
         match dialoguer::Input::<String>::new()
@@ -99,6 +164,15 @@ fn parse_host_interactively(ctx: &mut crate::context::Context) -> Result<url::Ur
 ///
 /// Alternatively, pass in a token on standard input by using `--with-token`.
 ///
+/// Logging in through the identity provider of a silo is not supported: the provider
+/// posts its assertion to the silo, not to `oxide`, so the session only exists in the
+/// browser. Use the device flow, and log in to the silo in the browser it opens.
+///
+/// The token is stored in the system keyring, or in a file encrypted with the
+/// passphrase in `OXIDE_PASSPHRASE` when there is no keyring. When neither is
+/// available, like in CI, it is stored in plaintext in the hosts file with a warning.
+/// Pass `--insecure-storage` to store it there without one.
+///
 ///     # start interactive setup
 ///     $ oxide auth login
 ///
@@ -110,6 +184,12 @@ fn parse_host_interactively(ctx: &mut crate::context::Context) -> Result<url::Ur
 ///
 ///     # authenticate with an insecure Oxide instance (not recommended)
 ///     $ oxide auth login --host http://oxide.internal
+///
+///     # authenticate a service account in CI with a mounted secret
+///     $ oxide auth login --host oxide.internal --token-file /run/secrets/oxide
+///
+///     # authenticate with a local omicron as a user, by its ID
+///     $ oxide auth login --host http://localhost:12220 --spoof 001de000-05e4-4000-8000-000000004007
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdAuthLogin {
@@ -122,20 +202,33 @@ pub struct CmdAuthLogin {
     /// as `http://`.
     #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
     pub host: Option<url::Url>,
-    // Open a browser to authenticate.
-    // TODO: Make this work when we have device auth.
-    // #[clap(short, long)]
-    // pub web: bool,
+
+    /// Store the token in plaintext in the hosts file instead of a credential store.
+    #[clap(long)]
+    pub insecure_storage: bool,
+
+    /// Log in as a user by its ID, for instances that allow spoofed tokens, like a
+    /// local omicron for development.
+    #[clap(long, value_name = "USER_ID", conflicts_with = "with-token")]
+    pub spoof: Option<String>,
+
+    /// Read the token from a file, like a secret mounted for a service account. The
+    /// file is read again by every command, so a rotated token is picked up.
+    #[clap(long, value_name = "PATH", conflicts_with_all = &["with-token", "spoof"])]
+    pub token_file: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdAuthLogin {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        if !ctx.io.can_prompt() && !self.with_token {
+        if !ctx.io.can_prompt() && !self.with_token && self.spoof.is_none() && self.token_file.is_none() {
             return Err(anyhow!("--with-token required when not running interactively"));
         }
 
         let mut token = String::new();
+        // A token read from standard input does not come with an expiry or a refresh token.
+        let mut expires_in = None;
+        let mut refresh_token = String::new();
 
         if self.with_token {
             // Read from stdin.
@@ -143,6 +236,19 @@ impl crate::cmd::Command for CmdAuthLogin {
             token = token.trim_end_matches('\n').to_string();
         }
 
+        // Keep the full path of the token file, since it is read from other directories.
+        let token_file = match &self.token_file {
+            Some(path) => {
+                let path = std::fs::canonicalize(path)
+                    .map_err(|err| anyhow!("could not read the token from {}: {}", path, err))?
+                    .to_string_lossy()
+                    .to_string();
+                token = crate::config_from_env::read_token_file(&path)?;
+                path
+            }
+            None => String::new(),
+        };
+
         let mut interactive = false;
         if ctx.io.can_prompt() && token.is_empty() {
             interactive = true;
@@ -177,6 +283,11 @@ impl crate::cmd::Command for CmdAuthLogin {
 
         let cs = ctx.io.color_scheme();
 
+        // A spoofed token doesn't need a login flow.
+        if let Some(user_id) = &self.spoof {
+            token = spoof_login(ctx, host, user_id).await?;
+        }
+
         // Do the login flow if we didn't get a token from stdin.
         if token.is_empty() {
             // We don't want to capture the error here just in case we have no host config
@@ -205,20 +316,15 @@ impl crate::cmd::Command for CmdAuthLogin {
             }
 
             // Do an OAuth 2.0 Device Authorization Grant dance to get a token.
-            let device_auth_url = DeviceAuthorizationUrl::new(format!("{}device/auth", host))?;
-            let client_id = ctx.config.get("", "client_id")?;
-            let auth_client = BasicClient::new(
-                ClientId::new(client_id),
-                None,
-                AuthUrl::new(format!("{}authorize", host))?,
-                Some(TokenUrl::new(format!("{}device/token", host))?),
-            )
-            .set_auth_type(AuthType::RequestBody)
-            .set_device_authorization_url(device_auth_url);
+            let auth_client = oauth_client(ctx, host)?;
+            let http = ctx
+                .http_client_builder(host)?
+                .redirect(reqwest::redirect::Policy::none())
+                .build()?;
 
             let details: StandardDeviceAuthorizationResponse = auth_client
                 .exchange_device_code()?
-                .request_async(async_http_client)
+                .request_async(|request| oauth_request(http.clone(), request))
                 .await?;
 
             if let Some(uri) = details.verification_uri_complete() {
@@ -240,29 +346,53 @@ impl crate::cmd::Command for CmdAuthLogin {
                 )?;
             }
 
-            token = auth_client
+            let response = auth_client
                 .exchange_device_access_token(&details)
-                .request_async(async_http_client, tokio::time::sleep, None)
-                .await?
-                .access_token()
-                .secret()
-                .to_string();
-        }
+                .request_async(|request| oauth_request(http.clone(), request), tokio::time::sleep, None)
+                .await?;
 
-        // Set the token in the config file.
-        ctx.config.set(host, "token", &token)?;
+            token = response.access_token().secret().to_string();
+            expires_in = response.expires_in();
+            if let Some(token) = response.refresh_token() {
+                refresh_token = token.secret().to_string();
+            }
+        }
 
-        let client = ctx.api_client(host)?;
+        let client = ctx.api_client_with_token(host, &token)?;
 
         // Get the session for the token.
         let session = client.hidden().session_me().await?;
 
-        // Set the user.
+        // Set the user. Other users logged into the host stay logged in, see
+        // `oxide auth switch`.
         // TODO: This should instead store the email, or some username or something
         // that is human knowable.
         let email = session.id;
         ctx.config.set(host, "user", &email)?;
 
+        let previous_token_file = ctx
+            .config
+            .get_user_with_source(host, &email, "token_file")
+            .unwrap_or_default()
+            .0;
+        if !token_file.is_empty() || !previous_token_file.is_empty() {
+            ctx.config.set_user(host, &email, "token_file", &token_file)?;
+        }
+
+        // Set the token in a credential store, or in the config file if asked to. The
+        // token from a token file is not stored, it is read from the file instead.
+        if token_file.is_empty() {
+            store_token(
+                ctx,
+                host,
+                &email,
+                &token,
+                expires_in,
+                &refresh_token,
+                self.insecure_storage,
+            )?;
+        }
+
         // Save the config.
         ctx.config.write()?;
 
@@ -272,6 +402,48 @@ impl crate::cmd::Command for CmdAuthLogin {
     }
 }
 
+/// Store the token of a user logged into a host in a credential store, or in the hosts
+/// file with `insecure_storage`.
+///
+/// When no credential store works, like in CI or a container without a keyring or
+/// `OXIDE_PASSPHRASE`, the token goes in the hosts file with a warning, as it did
+/// before there were credential stores.
+fn store_token(
+    ctx: &mut crate::context::Context,
+    host: &str,
+    user: &str,
+    token: &str,
+    expires_in: Option<std::time::Duration>,
+    refresh_token: &str,
+    insecure_storage: bool,
+) -> Result<()> {
+    let storage = if insecure_storage { "insecure" } else { "" };
+    ctx.config.set_user(host, user, "token_storage", storage)?;
+    let err = match ctx.set_token(host, user, token, expires_in, refresh_token) {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+
+    let reasons = match err.downcast_ref::<crate::credentials::NoCredentialStoreError>() {
+        Some(crate::credentials::NoCredentialStoreError(reasons)) => reasons.to_string(),
+        None => return Err(err),
+    };
+
+    let cs = ctx.io.color_scheme();
+    writeln!(
+        ctx.io.err_out,
+        "{} Could not store the token securely ({}), so it is stored in plaintext in {}. Set {} to store it \
+         in an encrypted file instead.",
+        cs.warning_icon(),
+        reasons,
+        crate::config_file::hosts_file()?,
+        crate::credentials::OXIDE_PASSPHRASE
+    )?;
+
+    ctx.config.set_user(host, user, "token_storage", "insecure")?;
+    ctx.set_token(host, user, token, expires_in, refresh_token)
+}
+
 /// Log out of an Oxide host.
 ///
 /// This command removes the authentication configuration for a host either specified
@@ -299,7 +471,7 @@ impl crate::cmd::Command for CmdAuthLogout {
 
         let candidates = ctx.config.hosts()?;
         if candidates.is_empty() {
-            return Err(anyhow!("not logged in to any hosts"));
+            return Err(crate::errors::AuthError("not logged in to any hosts".to_string()).into());
         }
 
         let hostname = if self.host.is_none() {
@@ -330,7 +502,7 @@ impl crate::cmd::Command for CmdAuthLogout {
             }
 
             if !found {
-                return Err(anyhow!("not logged into {}", hostname));
+                return Err(crate::errors::AuthError(format!("not logged into {}", hostname)).into());
             }
 
             hostname
@@ -353,7 +525,9 @@ impl crate::cmd::Command for CmdAuthLogout {
             return Err(err);
         }
 
-        let client = ctx.api_client(&hostname)?;
+        // Logging out should not refresh the token it is about to erase.
+        let token = ctx.config.get(&hostname, "token")?;
+        let client = ctx.api_client_with_token(&hostname, &token)?;
 
         // Get the current user.
         let session = client.hidden().session_me().await?;
@@ -396,6 +570,118 @@ impl crate::cmd::Command for CmdAuthLogout {
     }
 }
 
+/// Refresh the token for an Oxide host.
+///
+/// This gets a new token with the refresh token saved by `oxide auth login`, so you
+/// don't have to log in again. A token that expires within the hour is also refreshed
+/// before any other command talks to its host.
+///
+///     $ oxide auth refresh
+///
+///     $ oxide auth refresh --host oxide.internal
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAuthRefresh {
+    /// The hostname of the Oxide instance to refresh the token for.
+    #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
+    pub host: Option<url::Url>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAuthRefresh {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let hostname = if let Some(host) = &self.host {
+            host.as_str().to_string()
+        } else {
+            ctx.config.default_host()?
+        };
+
+        ctx.refresh_token(&hostname).await?;
+
+        let cs = ctx.io.color_scheme();
+        match ctx.token_expires_at(&hostname)? {
+            Some(expires_at) => writeln!(
+                ctx.io.out,
+                "{} Refreshed the token for {}, it expires {}",
+                cs.success_icon(),
+                cs.bold(&hostname),
+                chrono_humanize::HumanTime::from(expires_at)
+            )?,
+            None => writeln!(
+                ctx.io.out,
+                "{} Refreshed the token for {}",
+                cs.success_icon(),
+                cs.bold(&hostname)
+            )?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Switch the user logged into a host.
+///
+/// Several users can be logged into the same host, for example a fleet
+/// administrator and a tenant. Logging in as another user with `oxide auth login`
+/// keeps the users that were logged in before, and this command switches back to
+/// one of them. `oxide auth status` lists the users logged into each host.
+///
+/// Use another user for a single command with the global `--as` flag or the
+/// `OXIDE_USER` environment variable.
+///
+///     $ oxide auth switch --user 001de000-05e4-4000-8000-000000004007
+///
+///     # run a single command as another user
+///     $ oxide instance list --as 001de000-05e4-4000-8000-000000004007
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAuthSwitch {
+    /// The user to switch to.
+    #[clap(short, long, required = true)]
+    pub user: String,
+
+    /// The hostname of the Oxide instance to switch the user of.
+    #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
+    pub host: Option<url::Url>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAuthSwitch {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let hostname = if let Some(host) = &self.host {
+            host.as_str().to_string()
+        } else {
+            ctx.config.default_host()?
+        };
+
+        let users = ctx.config.users(&hostname)?;
+        if !users.contains(&self.user) {
+            return Err(crate::errors::AuthError(format!(
+                "{} is not logged into {}. Log in with `oxide auth login --host {}`, users logged in are: {}",
+                self.user,
+                hostname,
+                hostname,
+                users.join(", ")
+            ))
+            .into());
+        }
+
+        ctx.config.set(&hostname, "user", &self.user)?;
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Switched to {} on {}",
+            cs.success_icon(),
+            cs.bold(&self.user),
+            hostname
+        )?;
+
+        Ok(())
+    }
+}
+
 /// Verifies and displays information about your authentication state.
 ///
 /// This command will test your authentication state for each Oxide host that `oxide`
@@ -442,10 +728,27 @@ impl crate::cmd::Command for CmdAuthStatus {
 
             let (token, token_source) = ctx.config.get_with_source(hostname, "token")?;
 
-            let client = ctx.api_client(hostname)?;
-
             let mut host_status: Vec<String> = vec![];
 
+            let expires_at = ctx.token_expires_at(hostname)?;
+            if let Some(expires_at) = expires_at {
+                if expires_at <= chrono::Utc::now() {
+                    host_status.push(format!(
+                        "{} {}: token expired {}. Run {} to get a new one.",
+                        cs.failure_icon(),
+                        hostname,
+                        chrono_humanize::HumanTime::from(expires_at),
+                        cs.bold("oxide auth refresh")
+                    ));
+                    status_info.insert(hostname.to_string(), host_status);
+                    failed = true;
+                    continue;
+                }
+            }
+
+            // Check the token as it is, without refreshing it.
+            let client = ctx.api_client_with_token(hostname, &token)?;
+
             match client.hidden().session_me().await {
                 Ok(session) => {
                     // TODO: this should be the users email or something consistent with login
@@ -476,6 +779,23 @@ impl crate::cmd::Command for CmdAuthStatus {
                         token_display = token.to_string();
                     }
                     host_status.push(format!("{} Token: {}", cs.success_icon(), token_display));
+                    if token_source == crate::config_file::hosts_file()? {
+                        host_status.push(format!(
+                            "{} Token stored in plaintext in {}. Log in again without --insecure-storage to store it \
+                             securely.",
+                            cs.warning_icon(),
+                            token_source
+                        ));
+                    } else {
+                        host_status.push(format!("{} Token stored in {}", cs.success_icon(), token_source));
+                    }
+                    if let Some(expires_at) = expires_at {
+                        host_status.push(format!(
+                            "{} Token expires {}",
+                            cs.success_icon(),
+                            chrono_humanize::HumanTime::from(expires_at)
+                        ));
+                    }
                 }
                 Err(err) => {
                     host_status.push(format!("{} {}: api call failed: {}", cs.failure_icon(), hostname, err));
@@ -484,6 +804,19 @@ impl crate::cmd::Command for CmdAuthStatus {
                 }
             }
 
+            // List the other users logged into the host.
+            let user = ctx.config.get(hostname, "user").unwrap_or_default();
+            for other in ctx.config.users(hostname)? {
+                if other != user {
+                    host_status.push(format!(
+                        "{} Also logged in as {}, switch with {}",
+                        cs.success_icon(),
+                        cs.bold(&other),
+                        cs.bold(&format!("oxide auth switch --user {}", other))
+                    ));
+                }
+            }
+
             status_info.insert(hostname.to_string(), host_status);
         }
 
@@ -518,11 +851,89 @@ impl crate::cmd::Command for CmdAuthStatus {
     }
 }
 
+/// Print the auth token for an Oxide host.
+///
+/// This prints just the token, for use by other tools. The token comes from
+/// `OXIDE_TOKEN` if it is set, or from where `oxide auth login` stored it.
+///
+///     $ curl -H "Authorization: Bearer $(oxide auth token)" https://oxide.internal/session/me
+///
+///     # print the host and where the token comes from as well
+///     $ oxide auth token --host oxide.internal --format json
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdAuthToken {
+    /// The hostname of the Oxide instance to print the token for.
+    #[clap(short = 'H', long, env = "OXIDE_HOST", parse(try_from_str = parse_host))]
+    pub host: Option<url::Url>,
+
+    /// Print the token with its host and source in json, yaml, table, csv, or tsv format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+/// A token, as it is printed by `oxide auth token --format`.
+#[derive(serde::Serialize, Tabled)]
+struct Token {
+    host: String,
+    source: String,
+    token: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdAuthToken {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let hostname = if let Some(host) = &self.host {
+            host.as_str().to_string()
+        } else {
+            ctx.config.default_host()?
+        };
+
+        let (token, source) = match ctx.config.get_with_source(&hostname, "token") {
+            Ok((token, source)) if !token.is_empty() => (token, source),
+            _ => {
+                return Err(crate::errors::AuthError(format!(
+                    "no token found for {}. Run `oxide auth login --host {}` to log in.",
+                    hostname, hostname
+                ))
+                .into())
+            }
+        };
+
+        if let Some(expires_at) = ctx.token_expires_at(&hostname)? {
+            if expires_at <= chrono::Utc::now() {
+                let cs = ctx.io.color_scheme();
+                writeln!(
+                    ctx.io.err_out,
+                    "{} The token for {} expired {}. Run `oxide auth refresh` to get a new one.",
+                    cs.warning_icon(),
+                    hostname,
+                    chrono_humanize::HumanTime::from(expires_at)
+                )?;
+            }
+        }
+
+        match &self.format {
+            Some(format) => ctx.io.write_output(
+                format,
+                &Token {
+                    host: hostname,
+                    source,
+                    token,
+                },
+            )?,
+            None => writeln!(ctx.io.out, "{}", token)?,
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
 
-    use crate::cmd::Command;
+    use crate::{cmd::Command, config::Config};
 
     pub struct TestItem {
         name: String,
@@ -558,6 +969,9 @@ mod test {
                 cmd: crate::cmd_auth::SubCommand::Login(crate::cmd_auth::CmdAuthLogin {
                     host: Some(test_host.clone()),
                     with_token: false,
+                    insecure_storage: false,
+                    spoof: None,
+                    token_file: None,
                 }),
                 stdin: test_token.to_string(),
                 want_out: "".to_string(),
@@ -568,6 +982,9 @@ mod test {
                 cmd: crate::cmd_auth::SubCommand::Login(crate::cmd_auth::CmdAuthLogin {
                     host: Some(test_host.clone()),
                     with_token: true,
+                    insecure_storage: false,
+                    spoof: None,
+                    token_file: None,
                 }),
                 stdin: test_token.to_string(),
                 want_out: "✔ Logged in as ".to_string(),
@@ -707,4 +1124,184 @@ mod test {
             Ok(host) if host == "http://example.com:8888/"
         ));
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_cmd_auth_token() {
+        std::env::remove_var("OXIDE_HOST");
+        std::env::remove_var("OXIDE_TOKEN");
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let host = crate::cmd_auth::parse_host("oxide.example.com").unwrap();
+        let tests = vec![
+            (None, "", "no token found for https://oxide.example.com/"),
+            (None, "MY_TOKEN", "MY_TOKEN\n"),
+            (
+                Some(crate::types::FormatOutput::Json),
+                "MY_TOKEN",
+                "{\n  \"host\": \"https://oxide.example.com/\",\n  \"source\": \"the system keyring\",\n  \
+                 \"token\": \"MY_TOKEN\"\n}\n",
+            ),
+        ];
+
+        for (format, token, want) in tests {
+            if !token.is_empty() {
+                c.set(host.as_str(), "token", token).unwrap();
+            }
+
+            let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+            };
+
+            let cmd = crate::cmd_auth::CmdAuthToken {
+                host: Some(host.clone()),
+                format,
+            };
+            match cmd.run(&mut ctx).await {
+                Ok(()) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    assert_eq!(stdout, want);
+                }
+                Err(err) => assert!(err.to_string().starts_with(want), "{}", err),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_cmd_auth_switch() {
+        std::env::remove_var("OXIDE_HOST");
+        std::env::remove_var("OXIDE_TOKEN");
+        std::env::remove_var("OXIDE_USER");
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        let host = crate::cmd_auth::parse_host("oxide.example.com").unwrap();
+        c.set_user(host.as_str(), "admin", "token", "ADMIN_TOKEN").unwrap();
+        c.set_user(host.as_str(), "tenant", "token", "TENANT_TOKEN").unwrap();
+        c.set(host.as_str(), "user", "admin").unwrap();
+        assert_eq!(c.get(host.as_str(), "token").unwrap(), "ADMIN_TOKEN");
+
+        let tests = vec![
+            ("nobody", "", "nobody is not logged into https://oxide.example.com/"),
+            ("tenant", "✔ Switched to tenant on https://oxide.example.com/\n", ""),
+        ];
+
+        for (user, want_out, want_err) in tests {
+            let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+            };
+
+            let cmd = crate::cmd_auth::CmdAuthSwitch {
+                user: user.to_string(),
+                host: Some(host.clone()),
+            };
+            match cmd.run(&mut ctx).await {
+                Ok(()) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    assert_eq!(stdout, want_out, "user {}", user);
+                }
+                Err(err) => assert!(err.to_string().starts_with(want_err), "{}", err),
+            }
+        }
+
+        assert_eq!(c.get(host.as_str(), "token").unwrap(), "TENANT_TOKEN");
+
+        // `--as` uses another user for a single command.
+        c.set_override("user", "admin", "--as").unwrap();
+        assert_eq!(c.get(host.as_str(), "token").unwrap(), "ADMIN_TOKEN");
+    }
+
+    #[test]
+    fn test_store_token() {
+        let root = crate::config::new_blank_root().unwrap();
+        let mut config = crate::config::new_config(root, crate::credentials::Credentials::unavailable());
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (mut io, _, stderr_path) = crate::iostreams::IoStreams::test();
+        io.set_color_enabled(false);
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        // Without a keyring, the token goes in the hosts file rather than failing the
+        // login.
+        let host = "https://oxide.example.com/";
+        ctx.config.set(host, "user", "jess").unwrap();
+        crate::cmd_auth::store_token(&mut ctx, host, "jess", "MY_TOKEN", None, "", false).unwrap();
+        let get = |key| ctx.config.get_user_with_source(host, "jess", key).unwrap().0;
+        assert_eq!(get("token"), "MY_TOKEN");
+        assert_eq!(get("token_storage"), "insecure");
+
+        let stderr = std::fs::read_to_string(stderr_path).unwrap();
+        assert!(
+            stderr.starts_with(
+                "! Could not store the token securely (keyring: no system keyring found), so it is stored in \
+                 plaintext in "
+            ),
+            "{}",
+            stderr
+        );
+    }
+
+    /// A server that answers a single request with the given response, and returns the
+    /// request line it got.
+    async fn stub_server(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = tokio::io::BufReader::new(stream);
+            let mut request_line = String::new();
+            stream.read_line(&mut request_line).await.unwrap();
+            stream.write_all(response.as_bytes()).await.unwrap();
+            request_line.trim().to_string()
+        });
+
+        (host, handle)
+    }
+
+    #[tokio::test]
+    async fn test_spoof_login() {
+        let root = crate::config::new_blank_root().unwrap();
+        let mut config = crate::config::new_config(root, crate::credentials::Credentials::unavailable());
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, _, _) = crate::iostreams::IoStreams::test();
+        let ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        let user_id = "001de000-05e4-4000-8000-000000004007";
+        let (host, server) = stub_server("HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").await;
+        let token = crate::cmd_auth::spoof_login(&ctx, &host, user_id).await.unwrap();
+        assert_eq!(token, "oxide-spoof-001de000-05e4-4000-8000-000000004007");
+        assert_eq!(server.await.unwrap(), "POST /login HTTP/1.1");
+
+        // An instance that does not allow spoofed tokens fails the login.
+        let (host, _) = stub_server("HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n").await;
+        let err = crate::cmd_auth::spoof_login(&ctx, &host, user_id).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "could not log in to {} as {}: code: 401 Unauthorized, empty response",
+                host, user_id
+            )
+        );
+    }
 }
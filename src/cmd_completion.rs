@@ -62,6 +62,23 @@ impl crate::cmd::Command for CmdCompletion {
         // Convert our opts into a clap app.
         let mut app: Command = crate::Opts::command();
         let name = app.get_name().to_string();
+
+        // Complete the endpoints of `oxide api` with the paths of the API. The command
+        // is only used to generate the script, so they don't restrict what is accepted.
+        let spec = crate::api_spec::Spec::load()?;
+        let mut paths: Vec<String> = spec
+            .endpoints()
+            .iter()
+            .filter(|e| e.tag() != "hidden")
+            .map(|e| e.path.to_string())
+            .collect();
+        paths.dedup();
+        if let Some(api) = app.find_subcommand_mut("api") {
+            // The values need to live as long as the command, which is 'static.
+            let paths = paths.into_iter().map(|p| &*Box::leak(p.into_boxed_str()));
+            *api = api.clone().mut_arg("endpoint", |arg| arg.possible_values(paths));
+        }
+
         // Generate the completion script.
         generate(self.shell, &mut app, name, &mut ctx.io.out);
 
@@ -101,6 +118,12 @@ mod test {
                 want_out: "#compdef oxide".to_string(),
                 want_err: "".to_string(),
             },
+            TestItem {
+                name: "zsh completion of api endpoints".to_string(),
+                input: "zsh".to_string(),
+                want_out: "/organizations/{organization_name}/projects".to_string(),
+                want_err: "".to_string(),
+            },
             TestItem {
                 name: "fish completion".to_string(),
                 input: "fish".to_string(),
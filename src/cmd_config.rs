@@ -9,6 +9,16 @@ use clap::Parser;
 /// - prompt: toggle interactive prompting in the terminal (default: "enabled")
 /// - browser: the web browser to use for opening URLs
 /// - format: the formatting style for command output
+/// - ca_cert: a PEM file of certificate authorities to trust
+/// - client_cert: a PEM file with the client certificate to present
+/// - client_key: a PEM file with the private key of the client certificate
+/// - insecure: skip verifying the TLS certificate of a host (default: "false")
+/// - proxy: the proxy to reach hosts through
+/// - connect_timeout: how long to wait to connect to a host (default: "10s")
+/// - http_timeout: how long to wait for a response from a host (default: "60s")
+/// - retries: how many times to retry a request that failed for a passing reason (default: "3")
+///
+/// The TLS, proxy, timeout, and retry settings can also be set per host, with `--host`.
 #[derive(Parser, Debug, Clone)]
 #[clap(verbatim_doc_comment)]
 pub struct CmdConfig {
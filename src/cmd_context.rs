@@ -0,0 +1,243 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+/// Manage the current organization and project.
+///
+/// Commands that take `--organization` and `--project` use the current
+/// context when those flags are not passed. The context is stored per host
+/// in the hosts file. The `OXIDE_ORG` and `OXIDE_PROJECT` environment
+/// variables take precedence over it.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdContext {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    Set(CmdContextSet),
+    Show(CmdContextShow),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdContext {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::Set(cmd) => cmd.run(ctx).await,
+            SubCommand::Show(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Set the current organization and project.
+///
+/// Pass an empty value to clear it.
+///
+///     # use a project by default
+///     $ oxide context set --organization maze-war --project prod-online
+///
+///     # switch to another project in the same organization
+///     $ oxide context set --project development
+///
+///     # clear the current project
+///     $ oxide context set --project ""
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdContextSet {
+    /// The organization to use by default.
+    #[clap(long, short)]
+    pub organization: Option<String>,
+
+    /// The project to use by default.
+    #[clap(long, short)]
+    pub project: Option<String>,
+
+    /// Set the context for a specific host. Defaults to the default host.
+    #[clap(short = 'H', long, default_value = "")]
+    pub host: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdContextSet {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.organization.is_none() && self.project.is_none() {
+            bail!(crate::errors::InvalidInputError(
+                "--organization or --project required".to_string()
+            ));
+        }
+
+        let host = if self.host.is_empty() {
+            ctx.config.default_host()?
+        } else {
+            self.host.to_string()
+        };
+
+        let cs = ctx.io.color_scheme();
+        for (key, value) in [("organization", &self.organization), ("project", &self.project)] {
+            if let Some(value) = value {
+                ctx.config.set(&host, key, value)?;
+
+                if value.is_empty() {
+                    writeln!(
+                        ctx.io.out,
+                        "{} Cleared the current {} for {}",
+                        cs.success_icon(),
+                        key,
+                        host
+                    )?;
+                } else {
+                    writeln!(
+                        ctx.io.out,
+                        "{} Set the current {} for {} to {}",
+                        cs.success_icon(),
+                        key,
+                        host,
+                        value
+                    )?;
+                }
+            }
+        }
+
+        ctx.config.write()?;
+
+        Ok(())
+    }
+}
+
+/// Show the current organization and project, and where they are set.
+///
+///     $ oxide context show
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdContextShow {
+    /// Show the context for a specific host. Defaults to the default host.
+    #[clap(short = 'H', long, default_value = "")]
+    pub host: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdContextShow {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let host = if self.host.is_empty() {
+            ctx.config.default_host()?
+        } else {
+            self.host.to_string()
+        };
+
+        let cs = ctx.io.color_scheme();
+        writeln!(ctx.io.out, "{}", cs.bold(&host))?;
+        for key in ["organization", "project"] {
+            match ctx.config.get_with_source(&host, key) {
+                Ok((value, source)) if !value.is_empty() => writeln!(
+                    ctx.io.out,
+                    "  {}: {} {}",
+                    key,
+                    value,
+                    cs.gray(&format!("(from {})", source))
+                )?,
+                _ => writeln!(ctx.io.out, "  {}: {}", key, cs.gray("not set"))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::cmd::Command;
+
+    pub struct TestItem {
+        name: String,
+        cmd: crate::cmd_context::SubCommand,
+        want_out: String,
+        want_err: String,
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn test_cmd_context() {
+        let tests: Vec<TestItem> = vec![
+            TestItem {
+                name: "show empty".to_string(),
+                cmd: crate::cmd_context::SubCommand::Show(crate::cmd_context::CmdContextShow {
+                    host: "example.org".to_string(),
+                }),
+                want_out: "example.org\n  organization: not set\n  project: not set\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "set nothing".to_string(),
+                cmd: crate::cmd_context::SubCommand::Set(crate::cmd_context::CmdContextSet {
+                    organization: None,
+                    project: None,
+                    host: "example.org".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "--organization or --project required".to_string(),
+            },
+            TestItem {
+                name: "set organization and project".to_string(),
+                cmd: crate::cmd_context::SubCommand::Set(crate::cmd_context::CmdContextSet {
+                    organization: Some("maze-war".to_string()),
+                    project: Some("prod-online".to_string()),
+                    host: "example.org".to_string(),
+                }),
+                want_out: "✔ Set the current organization for example.org to maze-war\n✔ Set the current project for \
+                           example.org to prod-online\n"
+                    .to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "clear project".to_string(),
+                cmd: crate::cmd_context::SubCommand::Set(crate::cmd_context::CmdContextSet {
+                    organization: None,
+                    project: Some("".to_string()),
+                    host: "example.org".to_string(),
+                }),
+                want_out: "✔ Cleared the current project for example.org\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "show set".to_string(),
+                cmd: crate::cmd_context::SubCommand::Show(crate::cmd_context::CmdContextShow {
+                    host: "example.org".to_string(),
+                }),
+                want_out: "example.org\n  organization: maze-war (from ".to_string(),
+                want_err: "".to_string(),
+            },
+        ];
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        for t in tests {
+            let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+            };
+
+            let cmd_context = crate::cmd_context::CmdContext { subcmd: t.cmd };
+            match cmd_context.run(&mut ctx).await {
+                Ok(()) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    let stderr = std::fs::read_to_string(stderr_path).unwrap();
+                    assert!(stdout.contains(&t.want_out), "test {}: {}", t.name, stdout);
+                    assert!(stderr.is_empty(), "test {}", t.name);
+                }
+                Err(err) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    let stderr = std::fs::read_to_string(stderr_path).unwrap();
+                    assert_eq!(stdout, t.want_out, "test {}", t.name);
+                    assert!(err.to_string().contains(&t.want_err), "test {}", t.name);
+                    assert!(stderr.is_empty(), "test {}", t.name);
+                }
+            }
+        }
+    }
+}
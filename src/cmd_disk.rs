@@ -54,28 +54,31 @@ pub struct CmdDiskAttach {
     instance: String,
 
     /// The project that holds the disk and instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdDiskAttach {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let client = ctx.api_client("").await?;
+
+        let full_name = format!("{}/{}", organization, project);
 
         // Attach the disk.
         client
             .instances()
             .disks_attach(
                 &self.instance,
-                &self.organization,
-                &self.project,
+                &organization,
+                &project,
                 &oxide_api::types::DiskIdentifier {
                     name: self.disk.to_string(),
                 },
@@ -109,28 +112,31 @@ pub struct CmdDiskDetach {
     instance: String,
 
     /// The project that holds the disk and instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdDiskDetach {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Detach the disk.
         client
             .instances()
             .disks_detach(
                 &self.instance,
-                &self.organization,
-                &self.project,
+                &organization,
+                &project,
                 &oxide_api::types::DiskIdentifier {
                     name: self.disk.to_string(),
                 },
@@ -277,9 +283,12 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_disk::SubCommand::Delete(crate::cmd_disk::CmdDiskDelete {
-                    disk: "things".to_string(),
+                    disk: vec!["things".to_string()],
                     organization: "".to_string(),
                     project: "".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -294,6 +303,8 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                     sort_by: Default::default(),
                 }),
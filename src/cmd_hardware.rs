@@ -0,0 +1,189 @@
+use anyhow::Result;
+use clap::Parser;
+use tabled::Tabled;
+
+/// View the hardware that makes up an Oxide rack.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdHardware {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    Summary(CmdHardwareSummary),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdHardware {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::Summary(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Summarize the racks and sleds known to the control plane.
+///
+/// Displays one row per rack with the number of sleds in it and their
+/// service addresses.
+///
+/// The API does not report which rack a sled is in, so sleds are only
+/// attributed to a rack when the control plane knows about a single rack.
+/// Otherwise they are listed under an `unknown` rack.
+///
+///     $ oxide hardware summary
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdHardwareSummary {
+    /// Display output in json, yaml, table, csv, or tsv format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdHardwareSummary {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let client = ctx.api_client("").await?;
+
+        let racks = client
+            .racks()
+            .get_all(oxide_api::types::IdSortMode::IdAscending)
+            .await?;
+        let sleds = client
+            .sleds()
+            .get_all(oxide_api::types::IdSortMode::IdAscending)
+            .await?;
+
+        let format = ctx.format(&self.format)?;
+        ctx.io.write_output_for_vec(&format, summarize(&racks, &sleds))?;
+
+        Ok(())
+    }
+}
+
+/// A row of the hardware summary.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, Tabled)]
+pub struct HardwareSummary {
+    /// The ID of the rack, or `unknown` for sleds we could not place.
+    pub rack: String,
+    /// The number of sleds in the rack.
+    pub sleds: usize,
+    /// The service addresses of the sleds in the rack.
+    pub service_addresses: String,
+}
+
+/// Return the sleds in the given rack.
+///
+/// Sleds do not carry a rack ID, so we can only answer this when the rack is
+/// the only one the control plane knows about. Returns `None` otherwise.
+pub fn sleds_in_rack(
+    rack_id: &str,
+    racks: &[oxide_api::types::Rack],
+    sleds: &[oxide_api::types::Sled],
+) -> Option<Vec<oxide_api::types::Sled>> {
+    match racks {
+        [rack] if rack.id == rack_id => Some(sleds.to_vec()),
+        _ => None,
+    }
+}
+
+fn summarize(racks: &[oxide_api::types::Rack], sleds: &[oxide_api::types::Sled]) -> Vec<HardwareSummary> {
+    let row = |rack: &str, sleds: &[oxide_api::types::Sled]| HardwareSummary {
+        rack: rack.to_string(),
+        sleds: sleds.len(),
+        service_addresses: sleds
+            .iter()
+            .map(|s| s.service_address.clone())
+            .collect::<Vec<String>>()
+            .join(", "),
+    };
+
+    let mut summary = Vec::new();
+    let mut placed = false;
+    for rack in racks {
+        match sleds_in_rack(&rack.id, racks, sleds) {
+            Some(in_rack) => {
+                summary.push(row(&rack.id, &in_rack));
+                placed = true;
+            }
+            None => summary.push(row(&rack.id, &[])),
+        }
+    }
+
+    if !placed && !sleds.is_empty() {
+        summary.push(row("unknown", sleds));
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn rack(id: &str) -> oxide_api::types::Rack {
+        oxide_api::types::Rack {
+            id: id.to_string(),
+            time_created: Default::default(),
+            time_modified: Default::default(),
+        }
+    }
+
+    fn sled(id: &str, service_address: &str) -> oxide_api::types::Sled {
+        oxide_api::types::Sled {
+            id: id.to_string(),
+            service_address: service_address.to_string(),
+            time_created: Default::default(),
+            time_modified: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_summarize_single_rack() {
+        let racks = vec![rack("r1")];
+        let sleds = vec![sled("s1", "[fd00::1]:12345"), sled("s2", "[fd00::2]:12345")];
+
+        assert_eq!(sleds_in_rack("r1", &racks, &sleds).map(|s| s.len()), Some(2));
+        assert!(sleds_in_rack("r2", &racks, &sleds).is_none());
+        assert_eq!(
+            summarize(&racks, &sleds),
+            vec![HardwareSummary {
+                rack: "r1".to_string(),
+                sleds: 2,
+                service_addresses: "[fd00::1]:12345, [fd00::2]:12345".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_summarize_multiple_racks() {
+        let racks = vec![rack("r1"), rack("r2")];
+        let sleds = vec![sled("s1", "[fd00::1]:12345")];
+
+        assert!(sleds_in_rack("r1", &racks, &sleds).is_none());
+        assert_eq!(
+            summarize(&racks, &sleds),
+            vec![
+                HardwareSummary {
+                    rack: "r1".to_string(),
+                    sleds: 0,
+                    service_addresses: "".to_string(),
+                },
+                HardwareSummary {
+                    rack: "r2".to_string(),
+                    sleds: 0,
+                    service_addresses: "".to_string(),
+                },
+                HardwareSummary {
+                    rack: "unknown".to_string(),
+                    sleds: 1,
+                    service_addresses: "[fd00::1]:12345".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -56,11 +56,11 @@ pub struct CmdInstanceDisks {
     pub instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization to view the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 
     #[doc = r" Output format."]
@@ -71,14 +71,17 @@ pub struct CmdInstanceDisks {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceDisks {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
         let results = client
             .instances()
             .disks_get_all(
                 &self.instance,
-                &self.organization,
-                &self.project,
+                &organization,
+                &project,
                 oxide_api::types::NameSortMode::NameAscending,
             )
             .await?;
@@ -111,33 +114,36 @@ pub struct CmdInstanceStart {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 }
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceStart {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Start the instance.
         client
             .instances()
-            .start(&self.instance, &self.organization, &self.project)
+            .start(&self.instance, &organization, &project)
             .await?;
 
         // Wait for the instance to be started.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization,
+            project,
         };
 
         instance_state
@@ -166,11 +172,11 @@ pub struct CmdInstanceStop {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 
     /// Confirm stop without prompting.
@@ -185,9 +191,12 @@ impl crate::cmd::Command for CmdInstanceStop {
             return Err(anyhow!("--confirm required when not running interactively"));
         }
 
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let full_name = format!("{}/{}", organization, project);
 
         // Confirm stop.
         if !self.confirm {
@@ -207,17 +216,14 @@ impl crate::cmd::Command for CmdInstanceStop {
         }
 
         // Stop the instance.
-        client
-            .instances()
-            .stop(&self.instance, &self.organization, &self.project)
-            .await?;
+        client.instances().stop(&self.instance, &organization, &project).await?;
 
         // Wait for the instance to be stopped.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization,
+            project,
         };
 
         instance_state
@@ -246,11 +252,11 @@ pub struct CmdInstanceReboot {
     instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 
     /// Confirm reboot without prompting.
@@ -265,9 +271,12 @@ impl crate::cmd::Command for CmdInstanceReboot {
             return Err(anyhow!("--confirm required when not running interactively"));
         }
 
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
 
-        let full_name = format!("{}/{}", self.organization, self.project);
+        let client = ctx.api_client("").await?;
+
+        let full_name = format!("{}/{}", organization, project);
 
         // Confirm reboot.
         if !self.confirm {
@@ -289,15 +298,15 @@ impl crate::cmd::Command for CmdInstanceReboot {
         // Reboot the instance.
         client
             .instances()
-            .reboot(&self.instance, &self.organization, &self.project)
+            .reboot(&self.instance, &organization, &project)
             .await?;
 
         // Wait for the instance to be started.
         let instance_state = InstanceDetails {
             host: "".to_string(),
             instance: self.instance.to_string(),
-            organization: self.organization.to_string(),
-            project: self.project.to_string(),
+            organization,
+            project,
         };
 
         instance_state
@@ -336,7 +345,7 @@ impl InstanceDetails {
             .io
             .start_process_indicator_with_label(&format!(" Waiting for instance status to be `{}`", status));
 
-        let client = ctx.api_client(&self.host)?;
+        let client = ctx.api_client(&self.host).await?;
 
         // TODO: we should probably time out here eventually with an error.
         loop {
@@ -379,11 +388,11 @@ pub struct CmdInstanceSsh {
     pub args: Vec<String>,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 
     /// The ssh user. This defaults to `$USER` on the host the command is run on.
@@ -400,7 +409,10 @@ pub struct CmdInstanceSsh {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceSsh {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
         // Generate a key to use for ssh-ing into the instance.
         // We default to ed25519 here, since its a nice thing.
@@ -426,10 +438,7 @@ impl crate::cmd::Command for CmdInstanceSsh {
                 writeln!(ctx.io.out, "Adding temporary ssh key to your user account...")?;
         */
         // TODO: We need to get the instance IP address.
-        let _instance = client
-            .instances()
-            .get(&self.instance, &self.organization, &self.project)
-            .await?;
+        let _instance = client.instances().get(&self.instance, &organization, &project).await?;
 
         // Wrap the ssh command in a shell.
         std::process::Command::new("ssh")
@@ -460,11 +469,11 @@ pub struct CmdInstanceSerial {
     pub instance: String,
 
     /// The project that holds the instance.
-    #[clap(long, short, required = true)]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub project: String,
 
     /// The organization that holds the project.
-    #[clap(long, short, required = true, env = "OXIDE_ORG")]
+    #[clap(long, short, default_value = "", hide_default_value = true)]
     pub organization: String,
 
     /// The maximum length of bytes to retrieve.
@@ -484,7 +493,10 @@ pub struct CmdInstanceSerial {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdInstanceSerial {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let organization = ctx.organization(&self.organization)?;
+        let project = ctx.project(&self.project)?;
+
+        let client = ctx.api_client("").await?;
 
         let mut from_start = None;
         let mut most_recent = None;
@@ -505,8 +517,8 @@ impl crate::cmd::Command for CmdInstanceSerial {
                     &self.instance,
                     max_bytes,
                     most_recent,
-                    &self.organization,
-                    &self.project,
+                    &organization,
+                    &project,
                 )
                 .await?;
 
@@ -661,9 +673,12 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_instance::SubCommand::Delete(crate::cmd_instance::CmdInstanceDelete {
-                    instance: "things".to_string(),
+                    instance: vec!["things".to_string()],
                     organization: "".to_string(),
                     project: "".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -678,6 +693,8 @@ mod test {
                     organization: "".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                     sort_by: Default::default(),
                 }),
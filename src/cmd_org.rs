@@ -116,7 +116,10 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_org::SubCommand::Delete(crate::cmd_org::CmdOrganizationDelete {
-                    organization: "things".to_string(),
+                    organization: vec!["things".to_string()],
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -130,6 +133,8 @@ mod test {
                     sort_by: Default::default(),
                     limit: 0,
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                 }),
 
@@ -143,6 +148,8 @@ mod test {
                     sort_by: Default::default(),
                     limit: 30,
                     paginate: true,
+                    watch: false,
+                    interval: Default::default(),
                     format: Some(crate::types::FormatOutput::Json),
                 }),
 
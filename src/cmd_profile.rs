@@ -0,0 +1,377 @@
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use tabled::Tabled;
+
+/// Manage named profiles.
+///
+/// A profile bundles a host, a default organization and project, and an output
+/// format, so you can switch between environments like "prod" and "staging" with
+/// one command. Profiles are stored in the config file, while the credentials
+/// for each host stay in the hosts file.
+///
+/// Use a profile for a single command with the global `--profile` flag or the
+/// `OXIDE_PROFILE` environment variable, or for every command with
+/// `oxide profile use`. Environment variables like `OXIDE_HOST` and `OXIDE_ORG`
+/// still take precedence over the profile in use.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProfile {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    Create(CmdProfileCreate),
+    Delete(CmdProfileDelete),
+    List(CmdProfileList),
+    Use(CmdProfileUse),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProfile {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::Create(cmd) => cmd.run(ctx).await,
+            SubCommand::Delete(cmd) => cmd.run(ctx).await,
+            SubCommand::List(cmd) => cmd.run(ctx).await,
+            SubCommand::Use(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Create a profile, or update the values of an existing one.
+///
+///     # create a profile for production
+///     $ oxide profile create prod --host oxide.example.com --organization maze-war --project prod-online
+///
+///     # print json by default in the staging profile
+///     $ oxide profile create staging --host staging.example.com --format json
+///
+///     # clear the project of a profile
+///     $ oxide profile create prod --project ""
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProfileCreate {
+    /// The name of the profile.
+    #[clap(name = "name", required = true)]
+    pub name: String,
+
+    /// The host of the Oxide instance to use.
+    #[clap(short = 'H', long)]
+    pub host: Option<String>,
+
+    /// The organization to use by default.
+    #[clap(long, short)]
+    pub organization: Option<String>,
+
+    /// The project to use by default.
+    #[clap(long, short)]
+    pub project: Option<String>,
+
+    /// The output format to use by default.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProfileCreate {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.name.is_empty() {
+            bail!("the profile name cannot be empty");
+        }
+
+        let exists = ctx.config.profiles()?.contains(&self.name);
+
+        let host = match &self.host {
+            Some(host) if !host.is_empty() => Some(crate::cmd_auth::parse_host(host)?.to_string()),
+            host => host.clone(),
+        };
+        let format = self.format.as_ref().map(|format| format.to_string());
+
+        for (key, value) in [
+            ("host", &host),
+            ("organization", &self.organization),
+            ("project", &self.project),
+            ("format", &format),
+        ] {
+            if let Some(value) = value {
+                ctx.config.set_profile(&self.name, key, value)?;
+            } else if !exists {
+                // Write every key, so the profile shows what it can hold.
+                ctx.config.set_profile(&self.name, key, "")?;
+            }
+        }
+
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} {} profile {}",
+            cs.success_icon(),
+            if exists { "Updated" } else { "Created" },
+            self.name
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Delete a profile.
+///
+/// If the profile is in use, oxide goes back to using no profile.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProfileDelete {
+    /// The profile to delete.
+    #[clap(name = "name", required = true)]
+    pub name: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProfileDelete {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        ctx.config.unset_profile(&self.name)?;
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Deleted profile {}",
+            cs.success_icon_with_color(ansi_term::Color::Red),
+            self.name
+        )?;
+
+        Ok(())
+    }
+}
+
+/// A profile, as it is printed by `oxide profile list`.
+#[derive(serde::Serialize, Tabled)]
+struct Profile {
+    name: String,
+    current: bool,
+    host: String,
+    organization: String,
+    project: String,
+    format: String,
+}
+
+/// List your profiles.
+///
+/// The profile in use is marked as current.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProfileList {
+    /// Display output in json, yaml, table, csv, or tsv format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProfileList {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let (current, _) = ctx.config.current_profile_with_source()?;
+
+        let mut profiles = Vec::new();
+        for name in ctx.config.profiles()? {
+            profiles.push(Profile {
+                current: name == current,
+                host: ctx.config.get_profile(&name, "host")?,
+                organization: ctx.config.get_profile(&name, "organization")?,
+                project: ctx.config.get_profile(&name, "project")?,
+                format: ctx.config.get_profile(&name, "format")?,
+                name,
+            });
+        }
+
+        if profiles.is_empty() && self.format.is_none() {
+            writeln!(ctx.io.out, "no profiles configured")?;
+            return Ok(());
+        }
+
+        let format = ctx.format(&self.format)?;
+        ctx.io.write_output_for_vec(&format, &profiles)?;
+
+        Ok(())
+    }
+}
+
+/// Use a profile for every command.
+///
+/// Pass an empty value to stop using a profile.
+///
+///     $ oxide profile use staging
+///
+///     # go back to using no profile
+///     $ oxide profile use ""
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdProfileUse {
+    /// The profile to use.
+    #[clap(name = "name", required = true)]
+    pub name: String,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdProfileUse {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if !self.name.is_empty() && !ctx.config.profiles()?.contains(&self.name) {
+            return Err(anyhow!(
+                "profile `{}` not found. Create it with `oxide profile create {}`.",
+                self.name,
+                self.name
+            ));
+        }
+
+        ctx.config.set("", "profile", &self.name)?;
+        ctx.config.write()?;
+
+        let cs = ctx.io.color_scheme();
+        if self.name.is_empty() {
+            writeln!(ctx.io.out, "{} Stopped using a profile", cs.success_icon())?;
+        } else {
+            writeln!(ctx.io.out, "{} Using profile {}", cs.success_icon(), self.name)?;
+        }
+
+        let (current, source) = ctx.config.current_profile_with_source()?;
+        if current != self.name {
+            writeln!(
+                ctx.io.err_out,
+                "{} Profile {} from {} takes precedence over it",
+                cs.warning_icon(),
+                current,
+                source
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use crate::cmd::Command;
+
+    pub struct TestItem {
+        name: String,
+        cmd: crate::cmd_profile::SubCommand,
+        want_out: String,
+        want_err: String,
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_cmd_profile() {
+        std::env::remove_var("OXIDE_PROFILE");
+
+        let tests: Vec<TestItem> = vec![
+            TestItem {
+                name: "list empty".to_string(),
+                cmd: crate::cmd_profile::SubCommand::List(crate::cmd_profile::CmdProfileList { format: None }),
+                want_out: "no profiles configured\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "create".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Create(crate::cmd_profile::CmdProfileCreate {
+                    name: "prod".to_string(),
+                    host: Some("prod.example.com".to_string()),
+                    organization: Some("maze-war".to_string()),
+                    project: None,
+                    format: Some(crate::types::FormatOutput::Json),
+                }),
+                want_out: "✔ Created profile prod\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "update".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Create(crate::cmd_profile::CmdProfileCreate {
+                    name: "prod".to_string(),
+                    host: None,
+                    organization: None,
+                    project: Some("prod-online".to_string()),
+                    format: None,
+                }),
+                want_out: "✔ Updated profile prod\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "use missing".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Use(crate::cmd_profile::CmdProfileUse {
+                    name: "staging".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "profile `staging` not found".to_string(),
+            },
+            TestItem {
+                name: "use".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Use(crate::cmd_profile::CmdProfileUse {
+                    name: "prod".to_string(),
+                }),
+                want_out: "✔ Using profile prod\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "list".to_string(),
+                cmd: crate::cmd_profile::SubCommand::List(crate::cmd_profile::CmdProfileList {
+                    format: Some(crate::types::FormatOutput::Csv),
+                }),
+                want_out:
+                    "name,current,host,organization,project,format\nprod,true,https://prod.example.com/,maze-war,\
+                           prod-online,json\n"
+                        .to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "delete".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Delete(crate::cmd_profile::CmdProfileDelete {
+                    name: "prod".to_string(),
+                }),
+                want_out: "✔ Deleted profile prod\n".to_string(),
+                want_err: "".to_string(),
+            },
+            TestItem {
+                name: "delete missing".to_string(),
+                cmd: crate::cmd_profile::SubCommand::Delete(crate::cmd_profile::CmdProfileDelete {
+                    name: "prod".to_string(),
+                }),
+                want_out: "".to_string(),
+                want_err: "profile `prod` not found".to_string(),
+            },
+        ];
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        for t in tests {
+            let (mut io, stdout_path, stderr_path) = crate::iostreams::IoStreams::test();
+            io.set_color_enabled(false);
+            let mut ctx = crate::context::Context {
+                config: &mut c,
+                io,
+                debug: false,
+            };
+
+            let cmd_profile = crate::cmd_profile::CmdProfile { subcmd: t.cmd };
+            match cmd_profile.run(&mut ctx).await {
+                Ok(()) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    let stderr = std::fs::read_to_string(stderr_path).unwrap();
+                    assert_eq!(stdout, t.want_out, "test {}", t.name);
+                    assert!(stderr.is_empty(), "test {}", t.name);
+                }
+                Err(err) => {
+                    let stdout = std::fs::read_to_string(stdout_path).unwrap();
+                    let stderr = std::fs::read_to_string(stderr_path).unwrap();
+                    assert_eq!(stdout, t.want_out, "test {}", t.name);
+                    assert!(err.to_string().contains(&t.want_err), "test {}: {}", t.name, err);
+                    assert!(stderr.is_empty(), "test {}", t.name);
+                }
+            }
+        }
+    }
+}
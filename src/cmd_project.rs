@@ -87,8 +87,11 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_project::SubCommand::Delete(crate::cmd_project::CmdProjectDelete {
-                    project: "things".to_string(),
+                    project: vec!["things".to_string()],
                     organization: "".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -103,6 +106,8 @@ mod test {
                     limit: 0,
                     organization: "".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                 }),
 
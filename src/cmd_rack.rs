@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use clap::Parser;
 use cli_macro::crud_gen;
 
@@ -14,7 +14,10 @@ pub struct CmdRack {
     tag = "racks",
 }]
 #[derive(Parser, Debug, Clone)]
-enum SubCommand {}
+enum SubCommand {
+    #[clap(alias = "get")]
+    View(CmdRackView),
+}
 
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdRack {
@@ -25,3 +28,92 @@ impl crate::cmd::Command for CmdRack {
         }
     }
 }
+
+/// View rack.
+///
+/// Display information about an Oxide rack.
+///
+/// With `--sleds`, also list the sleds in the rack. The API does not report
+/// which rack a sled is in, so this only works when the control plane knows
+/// about a single rack.
+///
+/// With `--web`, open the rack in a web browser instead.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdRackView {
+    /// The rack to view. Can be an ID or name.
+    #[clap(name = "rack", required = true)]
+    pub rack: String,
+
+    /// Open the rack in the browser.
+    #[clap(short, long)]
+    pub web: bool,
+
+    /// List the sleds in the rack.
+    #[clap(long)]
+    pub sleds: bool,
+
+    /// Display output in json, yaml, table, csv, or tsv format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdRackView {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.web {
+            let url = format!("https://{}/{}", ctx.config.default_host()?, self.rack);
+            ctx.browser("", &url)?;
+            return Ok(());
+        }
+
+        let client = ctx.api_client("").await?;
+        let rack = client.racks().get(&self.rack).await?;
+        let format = ctx.format(&self.format)?;
+
+        if !self.sleds {
+            ctx.io.write_output(&format, &rack)?;
+            return Ok(());
+        }
+
+        let racks = client
+            .racks()
+            .get_all(oxide_api::types::IdSortMode::IdAscending)
+            .await?;
+        let all_sleds = client
+            .sleds()
+            .get_all(oxide_api::types::IdSortMode::IdAscending)
+            .await?;
+        let sleds = crate::cmd_hardware::sleds_in_rack(&rack.id, &racks, &all_sleds).ok_or_else(|| {
+            anyhow!(
+                "cannot list the sleds in rack {}: the control plane knows about {} racks and does not report which \
+                 rack a sled is in",
+                rack.id,
+                racks.len()
+            )
+        })?;
+
+        match format {
+            crate::types::FormatOutput::Table => {
+                ctx.io.write_output_table(&rack)?;
+                writeln!(ctx.io.out, "\nSleds")?;
+                ctx.io.write_output_table_for_vec(sleds)?;
+            }
+            crate::types::FormatOutput::Json => ctx.io.write_output_json(&with_sleds(&rack, &sleds)?)?,
+            crate::types::FormatOutput::Yaml => ctx.io.write_output_yaml(&with_sleds(&rack, &sleds)?)?,
+            // There is only room for one table, so list the sleds.
+            crate::types::FormatOutput::Csv | crate::types::FormatOutput::Tsv => {
+                ctx.io.write_output_for_vec(&format, sleds)?
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Nest the sleds under the rack for json and yaml output.
+fn with_sleds(rack: &oxide_api::types::Rack, sleds: &[oxide_api::types::Sled]) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(rack)?;
+    value["sleds"] = serde_json::to_value(sleds)?;
+    Ok(value)
+}
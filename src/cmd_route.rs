@@ -190,11 +190,14 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_route::SubCommand::Delete(crate::cmd_route::CmdRouteDelete {
-                    route: "things".to_string(),
+                    route: vec!["things".to_string()],
                     organization: "".to_string(),
                     project: "".to_string(),
                     vpc: "things".to_string(),
                     router: "blah".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -212,6 +215,8 @@ mod test {
                     project: "".to_string(),
                     router: "blah".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                 }),
 
@@ -121,10 +121,13 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_router::SubCommand::Delete(crate::cmd_router::CmdRouterDelete {
-                    router: "things".to_string(),
+                    router: vec!["things".to_string()],
                     organization: "".to_string(),
                     project: "".to_string(),
                     vpc: "things".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -141,6 +144,8 @@ mod test {
                     vpc: "things".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                 }),
 
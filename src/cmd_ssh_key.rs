@@ -79,7 +79,7 @@ impl crate::cmd::Command for CmdSSHKeyAdd {
                 .interact_text()?
         };
 
-        let client = ctx.api_client("")?;
+        let client = ctx.api_client("").await?;
         let params = SshKeyCreate {
             name: name.clone(),
             description,
@@ -113,7 +113,7 @@ pub struct CmdSSHKeyDelete {
 #[async_trait::async_trait]
 impl crate::cmd::Command for CmdSSHKeyDelete {
     async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
-        let client = ctx.api_client("")?;
+        let client = ctx.api_client("").await?;
         client.sshkeys().delete_key(&self.name).await?;
 
         let cs = ctx.io.color_scheme();
@@ -275,7 +275,7 @@ impl crate::cmd::Command for CmdSSHKeyList {
             return Err(anyhow!("--limit must be greater than 0"));
         }
 
-        let client = ctx.api_client("")?;
+        let client = ctx.api_client("").await?;
         let results = if self.paginate {
             client.sshkeys().get_all(NameSortMode::NameAscending).await?
         } else {
@@ -353,7 +353,7 @@ impl crate::cmd::Command for CmdSSHKeySyncFromGithub {
                 .collect::<Vec<String>>(),
         };
 
-        let client = ctx.api_client("")?;
+        let client = ctx.api_client("").await?;
         for (key, name) in keys.into_iter().zip(names) {
             let comment = if key.comment().is_empty() {
                 format!("From GitHub user {}", self.github_username)
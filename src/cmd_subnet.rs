@@ -134,10 +134,13 @@ mod test {
             TestItem {
                 name: "delete no --confirm non-interactive".to_string(),
                 cmd: crate::cmd_subnet::SubCommand::Delete(crate::cmd_subnet::CmdSubnetDelete {
-                    subnet: "things".to_string(),
+                    subnet: vec!["things".to_string()],
                     organization: "".to_string(),
                     project: "".to_string(),
                     vpc: "things".to_string(),
+                    all: false,
+                    filter: None,
+                    parallel: 4,
                     confirm: false,
                 }),
 
@@ -154,6 +157,8 @@ mod test {
                     vpc: "things".to_string(),
                     project: "".to_string(),
                     paginate: false,
+                    watch: false,
+                    interval: Default::default(),
                     format: None,
                 }),
 
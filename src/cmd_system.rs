@@ -0,0 +1,88 @@
+use anyhow::Result;
+use clap::Parser;
+
+/// Manage the system software of an Oxide rack.
+///
+/// These commands are meant for operators of the rack. To update the `oxide`
+/// command line tool itself, use `oxide update` instead.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdSystem {
+    #[clap(subcommand)]
+    subcmd: SubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum SubCommand {
+    Update(CmdSystemUpdate),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdSystem {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            SubCommand::Update(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Manage system software updates for the rack.
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdSystemUpdate {
+    #[clap(subcommand)]
+    subcmd: UpdateSubCommand,
+}
+
+#[derive(Parser, Debug, Clone)]
+enum UpdateSubCommand {
+    Refresh(CmdSystemUpdateRefresh),
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdSystemUpdate {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        match &self.subcmd {
+            UpdateSubCommand::Refresh(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+/// Refresh the update metadata of the rack.
+///
+/// This asks the control plane to fetch the latest update artifacts from its
+/// configured update repository. It does not apply any updates.
+///
+///     $ oxide system update refresh
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdSystemUpdateRefresh {}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdSystemUpdateRefresh {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        let host = ctx.config.default_host()?;
+        let client = ctx.api_client(&host).await?;
+
+        let handle = ctx.io.start_process_indicator_with_label(" Refreshing update metadata");
+
+        let result = client.updates().refresh().await;
+
+        if let Some(handle) = handle {
+            handle.done();
+        }
+
+        // Return any error from the control plane so `run_cmd` can print it nicely.
+        result?;
+
+        let cs = ctx.io.color_scheme();
+        writeln!(
+            ctx.io.out,
+            "{} Refreshed update metadata for the rack at {}",
+            cs.success_icon(),
+            host
+        )?;
+
+        Ok(())
+    }
+}
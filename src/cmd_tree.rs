@@ -0,0 +1,249 @@
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use futures::{StreamExt, TryStreamExt};
+
+/// Display organizations, projects, and their resources as a tree.
+///
+/// The tree is built by listing every organization, the projects in each
+/// organization, and the instances, disks, and VPCs in each project. Listing
+/// is done `--parallel` at a time, but it can still take a while on a busy rack,
+/// so use `--organization`, `--project`, and `--depth` to narrow it down.
+///
+/// Use `--format json` or `--format yaml` to get the tree as nested data.
+///
+///     # display everything you have access to
+///     $ oxide tree
+///
+///     # display a single project
+///     $ oxide tree -o my-org -p my-project
+///
+///     # display only organizations and projects
+///     $ oxide tree --depth 2
+#[derive(Parser, Debug, Clone)]
+#[clap(verbatim_doc_comment)]
+pub struct CmdTree {
+    /// Only display the given organization.
+    #[clap(long, short)]
+    pub organization: Option<String>,
+
+    /// Only display the given project. Requires `--organization`.
+    #[clap(long, short, requires = "organization")]
+    pub project: Option<String>,
+
+    /// How deep to walk: 1 for organizations, 2 for projects, 3 for resources.
+    #[clap(long, default_value = "3")]
+    pub depth: u32,
+
+    /// How many organizations or projects to list at once.
+    #[clap(long, default_value = "4")]
+    pub parallel: usize,
+
+    /// Display output in json, yaml, table, csv, or tsv format.
+    #[clap(long, short)]
+    pub format: Option<crate::types::FormatOutput>,
+}
+
+#[async_trait::async_trait]
+impl crate::cmd::Command for CmdTree {
+    async fn run(&self, ctx: &mut crate::context::Context) -> Result<()> {
+        if self.depth < 1 {
+            return Err(anyhow!("--depth must be greater than 0"));
+        }
+
+        let client = ctx.api_client("").await?;
+        let client = &client;
+
+        let organizations = match &self.organization {
+            Some(organization) => vec![organization.to_string()],
+            None => client
+                .organizations()
+                .get_all(oxide_api::types::NameOrIdSortMode::NameAscending)
+                .await?
+                .into_iter()
+                .map(|org| org.name.to_string())
+                .collect(),
+        };
+
+        let mut tree: Vec<TreeNode> = organizations
+            .iter()
+            .map(|organization| TreeNode::new("organization", organization))
+            .collect();
+
+        if self.depth >= 2 {
+            let projects: Vec<Vec<String>> = futures::stream::iter(organizations)
+                .map(|organization| async move { self.projects(client, &organization).await })
+                .buffered(self.parallel.max(1))
+                .try_collect()
+                .await?;
+
+            for (node, projects) in tree.iter_mut().zip(projects) {
+                node.children = projects
+                    .iter()
+                    .map(|project| TreeNode::new("project", project))
+                    .collect();
+            }
+        }
+
+        if self.depth >= 3 {
+            // List the resources of the projects of every organization together, so no
+            // more than `--parallel` projects are listed at once overall.
+            let projects: Vec<(String, String)> = tree
+                .iter()
+                .flat_map(|node| {
+                    node.children
+                        .iter()
+                        .map(|project| (node.name.to_string(), project.name.to_string()))
+                })
+                .collect();
+
+            let resources: Vec<Vec<TreeNode>> = futures::stream::iter(projects)
+                .map(|(organization, project)| async move { self.resources(client, &organization, &project).await })
+                .buffered(self.parallel.max(1))
+                .try_collect()
+                .await?;
+
+            let projects = tree.iter_mut().flat_map(|node| node.children.iter_mut());
+            for (project, resources) in projects.zip(resources) {
+                project.children = resources;
+            }
+        }
+
+        let format = ctx.format(&self.format)?;
+        match format {
+            crate::types::FormatOutput::Json => ctx.io.write_output_json(&serde_json::to_value(&tree)?)?,
+            crate::types::FormatOutput::Yaml => ctx.io.write_output_yaml(&tree)?,
+            crate::types::FormatOutput::Table => {
+                let cs = ctx.io.color_scheme();
+                write!(ctx.io.out, "{}", render(&tree, &cs))?;
+            }
+            crate::types::FormatOutput::Csv | crate::types::FormatOutput::Tsv => {
+                return Err(anyhow!(
+                    "--format {} is not supported for a tree, use json or yaml",
+                    format
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CmdTree {
+    /// The names of the projects of an organization to display.
+    async fn projects(&self, client: &oxide_api::Client, organization: &str) -> Result<Vec<String>> {
+        Ok(match &self.project {
+            Some(project) => vec![project.to_string()],
+            None => client
+                .projects()
+                .get_all(organization, oxide_api::types::NameOrIdSortMode::NameAscending)
+                .await?
+                .into_iter()
+                .map(|project| project.name.to_string())
+                .collect(),
+        })
+    }
+
+    /// The instances, disks, and VPCs of a project.
+    async fn resources(&self, client: &oxide_api::Client, organization: &str, project: &str) -> Result<Vec<TreeNode>> {
+        let sort = oxide_api::types::NameSortMode::NameAscending;
+        // The resources borrow the client they come from, so they have to outlive the
+        // requests.
+        let (instances_api, disks_api, vpcs_api) = (client.instances(), client.disks(), client.vpcs());
+        let (instances, disks, vpcs) = tokio::try_join!(
+            instances_api.get_all(organization, project, sort.clone()),
+            disks_api.get_all(organization, project, sort.clone()),
+            vpcs_api.get_all(organization, project, sort),
+        )?;
+
+        let mut resources: Vec<TreeNode> = instances
+            .iter()
+            .map(|instance| TreeNode::new("instance", &instance.name.to_string()))
+            .collect();
+        resources.extend(disks.iter().map(|disk| TreeNode::new("disk", &disk.name.to_string())));
+        resources.extend(vpcs.iter().map(|vpc| TreeNode::new("vpc", &vpc.name.to_string())));
+
+        Ok(resources)
+    }
+}
+
+/// A resource in the tree.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TreeNode {
+    /// The kind of resource, for example `organization` or `instance`.
+    pub kind: String,
+    /// The name of the resource.
+    pub name: String,
+    /// The resources that belong to this one.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(kind: &str, name: &str) -> Self {
+        TreeNode {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            children: Default::default(),
+        }
+    }
+}
+
+/// Render the tree with one resource per line, indented under its parent.
+fn render(tree: &[TreeNode], cs: &crate::colors::ColorScheme) -> String {
+    let mut out = String::new();
+    for node in tree {
+        out.push_str(&format!("{}\n", cs.bold(&node.name)));
+        render_children(&node.children, "", cs, &mut out);
+    }
+    out
+}
+
+fn render_children(children: &[TreeNode], prefix: &str, cs: &crate::colors::ColorScheme, out: &mut String) {
+    for (i, node) in children.iter().enumerate() {
+        let last = i == children.len() - 1;
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        // Projects are directly under organizations, so only label the leaves.
+        let label = if node.kind == "project" {
+            node.name.to_string()
+        } else {
+            format!("{} {}", node.name, cs.gray(&format!("({})", node.kind)))
+        };
+
+        out.push_str(&format!("{}{}{}\n", prefix, branch, label));
+        render_children(&node.children, &format!("{}{}", prefix, indent), cs, out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut project = TreeNode::new("project", "prod");
+        project.children = vec![TreeNode::new("instance", "web"), TreeNode::new("disk", "data")];
+        let mut org = TreeNode::new("organization", "maze-war");
+        org.children = vec![project, TreeNode::new("project", "dev")];
+        let tree = vec![org, TreeNode::new("organization", "empty")];
+
+        let cs = crate::colors::ColorScheme::new(false, false, false);
+
+        assert_eq!(
+            render(&tree, &cs),
+            r#"maze-war
+├── prod
+│   ├── web (instance)
+│   └── data (disk)
+└── dev
+empty
+"#
+        );
+    }
+}
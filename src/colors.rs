@@ -127,7 +127,6 @@ impl ColorScheme {
         self.yellow("!")
     }
 
-    #[allow(dead_code)]
     pub fn failure_icon(&self) -> String {
         self.red("✘")
     }
@@ -30,9 +30,31 @@ pub trait Config {
     /// of running `oxide` itself.
     fn expand_alias(&mut self, args: Vec<String>) -> Result<(Vec<String>, bool)>;
 
+    /// Get the names of the profiles.
+    fn profiles(&self) -> Result<Vec<String>>;
+    /// Returns a value from a profile by its key.
+    fn get_profile(&self, profile: &str, key: &str) -> Result<String>;
+    /// Sets a value in a profile by its key, creating the profile if it does not exist.
+    fn set_profile(&mut self, profile: &str, key: &str, value: &str) -> Result<()>;
+    /// Remove a profile.
+    fn unset_profile(&mut self, profile: &str) -> Result<()>;
+    /// Get the profile in use with the source. The name is empty if no profile is in use.
+    fn current_profile_with_source(&self) -> Result<(String, String)>;
+
+    /// Get the users logged into a host.
+    fn users(&self, hostname: &str) -> Result<Vec<String>>;
+    /// Returns a value of a user logged into a host, like its token, with the source.
+    fn get_user_with_source(&self, hostname: &str, user: &str, key: &str) -> Result<(String, String)>;
+    /// Sets a value of a user logged into a host.
+    fn set_user(&mut self, hostname: &str, user: &str, key: &str, value: &str) -> Result<()>;
+
     /// Check if the configuration can be written to.
     fn check_writable(&self, hostname: &str, key: &str) -> Result<()>;
 
+    /// Override a value for this command only, like with `--profile`, with the flag as
+    /// the source. It takes precedence over everything else and is never written.
+    fn set_override(&mut self, key: &str, value: &str, source: &str) -> Result<()>;
+
     /// Write the configuration.
     fn write(&self) -> Result<()>;
 
@@ -91,9 +113,90 @@ pub fn config_options() -> Vec<ConfigOption> {
             default_value: Uuid::new_v4().to_string(),
             allowed_values: vec![],
         },
+        ConfigOption {
+            key: "ca_cert".to_string(),
+            description: "a PEM file of certificate authorities to trust".to_string(),
+            comment: "A PEM file of certificate authorities to trust besides the system ones, for hosts with an \
+                      internal CA. Set it per host with `oxide config set -H <host>`."
+                .to_string(),
+            default_value: "".to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "client_cert".to_string(),
+            description: "a PEM file with the client certificate to present".to_string(),
+            comment: "A PEM file with the client certificate to present to hosts that ask for one, along with \
+                      `client_key`."
+                .to_string(),
+            default_value: "".to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "client_key".to_string(),
+            description: "a PEM file with the private key of the client certificate".to_string(),
+            comment: "A PEM file with the private key of `client_cert`.".to_string(),
+            default_value: "".to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "insecure".to_string(),
+            description: "skip verifying the TLS certificate of a host".to_string(),
+            comment: "Whether to skip verifying the TLS certificate of a host. Only use this for hosts you trust, \
+                      like a rack in a lab."
+                .to_string(),
+            default_value: "false".to_string(),
+            allowed_values: vec!["true".to_string(), "false".to_string()],
+        },
+        ConfigOption {
+            key: "proxy".to_string(),
+            description: "the proxy to reach hosts through".to_string(),
+            comment: "The proxy to reach hosts through, like `http://proxy.example.com:3128`. If blank, will refer \
+                      to HTTPS_PROXY. Hosts in NO_PROXY are reached directly."
+                .to_string(),
+            default_value: "".to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "connect_timeout".to_string(),
+            description: "how long to wait to connect to a host".to_string(),
+            comment: "How long to wait to connect to a host, like `10s`.".to_string(),
+            default_value: crate::context::DEFAULT_CONNECT_TIMEOUT.to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "http_timeout".to_string(),
+            description: "how long to wait for a response from a host".to_string(),
+            comment: "How long to wait for a response from a host, like `60s`. Override it for a single command \
+                      with `--timeout`."
+                .to_string(),
+            default_value: crate::context::DEFAULT_HTTP_TIMEOUT.to_string(),
+            allowed_values: vec![],
+        },
+        ConfigOption {
+            key: "retries".to_string(),
+            description: "how many times to retry a request that failed for a passing reason".to_string(),
+            comment: "How many times to retry a request that could not connect or that the host could not serve \
+                      right then. Override it for a single command with `--retries`."
+                .to_string(),
+            default_value: crate::retry::DEFAULT_RETRIES.to_string(),
+            allowed_values: vec![],
+        },
     ]
 }
 
+/// The keys a profile can hold. Everything else, like the token, is stored per host.
+pub const PROFILE_KEYS: [&str; 4] = ["host", "organization", "project", "format"];
+
+/// The keys stored for each user logged into a host. Getting or setting them on a host
+/// uses the `user` of the host.
+pub const ACCOUNT_KEYS: [&str; 5] = [
+    "token",
+    "token_storage",
+    "token_expires_at",
+    "refresh_token",
+    "token_file",
+];
+
 pub fn validate_key(key: &str) -> Result<()> {
     for config_key in config_options() {
         if key == config_key.key {
@@ -111,6 +214,18 @@ pub enum InvalidValueError {
 }
 
 pub fn validate_value(key: &str, value: &str) -> Result<()> {
+    if key.ends_with("_timeout") && !value.is_empty() {
+        value.parse::<crate::types::Interval>()?;
+        return Ok(());
+    }
+
+    if key == "retries" && !value.is_empty() {
+        value
+            .parse::<u32>()
+            .map_err(|_| anyhow!("invalid retries `{}`, for example use `3`", value))?;
+        return Ok(());
+    }
+
     let mut valid_values: Vec<String> = vec![];
 
     // Set the valid values for the key.
@@ -138,14 +253,15 @@ pub fn validate_value(key: &str, value: &str) -> Result<()> {
 #[cfg(test)]
 fn new_from_string(s: &str) -> Result<impl Config> {
     let root = s.parse::<toml_edit::Document>()?;
-    Ok(new_config(root))
+    Ok(new_config(root, crate::credentials::Credentials::mock()))
 }
 
-pub fn new_config(t: toml_edit::Document) -> impl Config {
+pub fn new_config(t: toml_edit::Document, credentials: crate::credentials::Credentials) -> impl Config {
     crate::config_from_file::FileConfig {
         map: crate::config_map::ConfigMap {
             root: t.as_table().clone(),
         },
+        credentials,
     }
 }
 
@@ -167,7 +283,7 @@ pub fn new_blank_root() -> Result<toml_edit::Document> {
 #[cfg(test)]
 pub fn new_blank_config() -> Result<impl Config> {
     let root = new_blank_root()?;
-    Ok(new_config(root))
+    Ok(new_config(root, crate::credentials::Credentials::mock()))
 }
 
 #[cfg(test)]
@@ -232,7 +348,7 @@ prompt = "enabled"
 browser = ""
 
 # What formatting Oxide should use when printing text.
-# Supported values: table, json, yaml
+# Supported values: table, json, yaml, csv, tsv
 format = "table""#;
         assert!(doc_config.contains(expected));
 
@@ -333,6 +449,18 @@ default = true"#;
 
         let result = validate_value("prompt", "enabled");
         assert!(result.is_ok());
+
+        let result = validate_value("http_timeout", "30s");
+        assert!(result.is_ok());
+
+        let result = validate_value("connect_timeout", "soon").unwrap_err();
+        assert_eq!(result.to_string(), "invalid interval `soon`, for example use `2s`");
+
+        let result = validate_value("retries", "5");
+        assert!(result.is_ok());
+
+        let result = validate_value("retries", "-1").unwrap_err();
+        assert_eq!(result.to_string(), "invalid retries `-1`, for example use `3`");
     }
 
     pub struct TestItem {
@@ -489,4 +617,256 @@ default = true"#;
             }
         }
     }
+
+    #[test]
+    fn test_token_storage() {
+        let mut c = new_blank_config().unwrap();
+
+        // Tokens are stored in the keyring by default.
+        c.set("example.com", "user", "jess").unwrap();
+        c.set("example.com", "token", "MY_TOKEN").unwrap();
+        assert_eq!(
+            c.get_with_source("example.com", "token").unwrap(),
+            ("MY_TOKEN".to_string(), "the system keyring".to_string())
+        );
+
+        // Unless the host was logged into with `--insecure-storage`.
+        c.set("oxide.computer", "token_storage", "insecure").unwrap();
+        c.set("oxide.computer", "token", "OTHER_TOKEN").unwrap();
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "OTHER_TOKEN");
+
+        let doc = c.hosts_to_string().unwrap();
+        assert!(doc.contains(
+            r#"["example.com".users.jess]
+token_storage = "keyring""#
+        ));
+        assert!(doc.contains(
+            r#"["oxide.computer"]
+token_storage = "insecure"
+token = "OTHER_TOKEN""#
+        ));
+
+        // Logging in again securely moves the token out of the hosts file.
+        c.set("oxide.computer", "token_storage", "").unwrap();
+        c.set("oxide.computer", "token", "NEW_TOKEN").unwrap();
+        assert!(!c.hosts_to_string().unwrap().contains("NEW_TOKEN"));
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "NEW_TOKEN");
+
+        // Logging out removes the token from the keyring.
+        c.unset_host("example.com").unwrap();
+        c.set("example.com", "token_storage", "keyring").unwrap();
+        assert!(c.get("example.com", "token").is_err());
+    }
+
+    #[test]
+    fn test_users() {
+        let mut c = new_from_string(
+            r#"[hosts."oxide.computer"]
+user = "jess"
+token_storage = "insecure"
+token = "JESS_TOKEN""#,
+        )
+        .unwrap();
+
+        // A host logged into before it could have several users has its token in the host.
+        assert_eq!(c.users("oxide.computer").unwrap(), vec!["jess".to_string()]);
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "JESS_TOKEN");
+
+        // Logging in as another user keeps the previous one.
+        c.set("oxide.computer", "user", "sam").unwrap();
+        c.set_user("oxide.computer", "sam", "token", "SAM_TOKEN").unwrap();
+        assert_eq!(
+            c.users("oxide.computer").unwrap(),
+            vec!["jess".to_string(), "sam".to_string()]
+        );
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "SAM_TOKEN");
+        assert_eq!(
+            c.get_user_with_source("oxide.computer", "jess", "token").unwrap().0,
+            "JESS_TOKEN"
+        );
+
+        let doc = c.hosts_to_string().unwrap();
+        assert!(doc.contains(
+            r#"["oxide.computer".users.jess]
+token = "JESS_TOKEN"
+token_storage = "insecure""#
+        ));
+        assert!(!doc.contains("SAM_TOKEN"));
+
+        // Switching back uses the token of the previous user.
+        c.set("oxide.computer", "user", "jess").unwrap();
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "JESS_TOKEN");
+
+        // Logging out removes every user.
+        c.unset_host("oxide.computer").unwrap();
+        assert!(c.users("oxide.computer").is_err());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_token_file() {
+        std::env::remove_var("OXIDE_TOKEN");
+        std::env::remove_var("OXIDE_USER");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oxide");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "FILE_TOKEN\n").unwrap();
+
+        let mut config = new_blank_config().unwrap();
+        config.set("oxide.computer", "token", "STORED_TOKEN").unwrap();
+        let c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        std::env::set_var("OXIDE_TOKEN_FILE", path);
+        assert_eq!(
+            c.get_with_source("oxide.computer", "token").unwrap(),
+            ("FILE_TOKEN".to_string(), path.to_string())
+        );
+
+        // The file is read again, so a rotated token is picked up.
+        std::fs::write(path, "ROTATED_TOKEN").unwrap();
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "ROTATED_TOKEN");
+
+        let err = c.check_writable("oxide.computer", "token").unwrap_err();
+        assert_eq!(err.to_string(), "read-only value in: OXIDE_TOKEN_FILE");
+
+        // `OXIDE_TOKEN` takes precedence over the file.
+        std::env::set_var("OXIDE_TOKEN", "ENV_TOKEN");
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "ENV_TOKEN");
+        std::env::remove_var("OXIDE_TOKEN");
+
+        std::env::set_var("OXIDE_TOKEN_FILE", dir.path().join("missing").to_str().unwrap());
+        let err = c.get("oxide.computer", "token").unwrap_err();
+        assert!(err.to_string().starts_with("could not read the token from "), "{}", err);
+
+        std::env::remove_var("OXIDE_TOKEN_FILE");
+        assert_eq!(c.get("oxide.computer", "token").unwrap(), "STORED_TOKEN");
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut c = new_blank_config().unwrap();
+        assert!(c.profiles().unwrap().is_empty());
+        assert_eq!(c.current_profile_with_source().unwrap().0, "");
+
+        c.set_profile("prod", "host", "https://prod.example.com/").unwrap();
+        c.set_profile("prod", "organization", "maze-war").unwrap();
+        c.set_profile("staging", "format", "json").unwrap();
+        c.set("", "profile", "prod").unwrap();
+
+        assert_eq!(c.profiles().unwrap(), vec!["prod".to_string(), "staging".to_string()]);
+        assert_eq!(c.get_profile("prod", "organization").unwrap(), "maze-war");
+        assert_eq!(c.get_profile("prod", "project").unwrap(), "");
+        assert_eq!(c.current_profile_with_source().unwrap().0, "prod");
+
+        let doc = c.config_to_string().unwrap();
+        assert!(doc.contains(
+            r#"[profiles.prod]
+host = "https://prod.example.com/"
+organization = "maze-war"
+
+[profiles.staging]
+format = "json""#
+        ));
+        assert!(!doc.contains("[profiles]\n"));
+
+        // Deleting the current profile stops using it.
+        c.unset_profile("prod").unwrap();
+        assert_eq!(c.profiles().unwrap(), vec!["staging".to_string()]);
+        assert_eq!(c.current_profile_with_source().unwrap().0, "");
+
+        let err = c.get_profile("prod", "host").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "profile `prod` not found. Create it with `oxide profile create prod`."
+        );
+        assert!(c.unset_profile("prod").is_err());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_profile_resolution() {
+        for var in [
+            "OXIDE_PROFILE",
+            "OXIDE_HOST",
+            "OXIDE_ORG",
+            "OXIDE_ORGANIZATION",
+            "OXIDE_PROJECT",
+            "OXIDE_FORMAT",
+        ] {
+            std::env::remove_var(var);
+        }
+
+        let mut config = crate::config::new_from_string(
+            r#"format = "table"
+
+[hosts."https://prod.example.com/"]
+organization = "prod-org"
+project = "prod-project"
+
+[hosts."https://staging.example.com/"]
+default = true
+organization = "staging-org""#,
+        )
+        .unwrap();
+        config.set_profile("prod", "host", "prod.example.com").unwrap();
+        config.set_profile("prod", "organization", "maze-war").unwrap();
+        config.set_profile("prod", "format", "json").unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+
+        // Without a profile, the default host and its context are used.
+        assert_eq!(c.default_host().unwrap(), "https://staging.example.com/");
+        assert_eq!(c.get("", "format").unwrap(), "table");
+
+        std::env::set_var("OXIDE_PROFILE", "prod");
+        assert_eq!(
+            c.current_profile_with_source().unwrap(),
+            ("prod".to_string(), "OXIDE_PROFILE".to_string())
+        );
+        assert_eq!(
+            c.default_host_with_source().unwrap(),
+            ("https://prod.example.com/".to_string(), "profile prod".to_string())
+        );
+        assert_eq!(c.get("https://prod.example.com/", "organization").unwrap(), "maze-war");
+        // The profile does not set a project, so the one of the host is used.
+        assert_eq!(c.get("https://prod.example.com/", "project").unwrap(), "prod-project");
+        // The profile only applies to its own host.
+        assert_eq!(
+            c.get("https://staging.example.com/", "organization").unwrap(),
+            "staging-org"
+        );
+        assert_eq!(c.get("", "format").unwrap(), "json");
+
+        // Environment variables take precedence over the profile.
+        std::env::set_var("OXIDE_ORG", "other-org");
+        std::env::set_var("OXIDE_HOST", "staging.example.com");
+        assert_eq!(c.get("https://prod.example.com/", "organization").unwrap(), "other-org");
+        assert_eq!(c.default_host().unwrap(), "https://staging.example.com/");
+        // `OXIDE_ORGANIZATION` works too, but `OXIDE_ORG` comes first.
+        std::env::set_var("OXIDE_ORGANIZATION", "another-org");
+        assert_eq!(
+            c.get_with_source("https://prod.example.com/", "organization").unwrap(),
+            ("other-org".to_string(), "OXIDE_ORG".to_string())
+        );
+        std::env::remove_var("OXIDE_ORG");
+        assert_eq!(
+            c.get_with_source("https://prod.example.com/", "organization").unwrap(),
+            ("another-org".to_string(), "OXIDE_ORGANIZATION".to_string())
+        );
+        std::env::remove_var("OXIDE_ORGANIZATION");
+        std::env::remove_var("OXIDE_HOST");
+
+        std::env::set_var("OXIDE_PROFILE", "missing");
+        assert!(c.default_host().is_err());
+
+        // `--profile` takes precedence over `OXIDE_PROFILE`.
+        c.set_override("profile", "prod", "--profile").unwrap();
+        assert_eq!(
+            c.current_profile_with_source().unwrap(),
+            ("prod".to_string(), "--profile".to_string())
+        );
+        assert_eq!(c.default_host().unwrap(), "https://prod.example.com/");
+
+        std::env::remove_var("OXIDE_PROFILE");
+    }
 }
@@ -137,6 +137,17 @@ pub fn hosts_file() -> Result<String> {
     }
 }
 
+pub fn credentials_file() -> Result<String> {
+    let config_dir = config_dir()?;
+    let path = Path::new(&config_dir).join("credentials.toml");
+
+    // Convert the path into a string slice
+    match path.to_str() {
+        None => return Err(anyhow!("path is not a valid UTF-8 sequence")),
+        Some(s) => Ok(s.to_string()),
+    }
+}
+
 pub fn state_file() -> Result<String> {
     let state_dir = state_dir()?;
     let path = Path::new(&state_dir).join("state.toml");
@@ -172,7 +183,10 @@ pub fn parse_default_config() -> Result<impl crate::config::Config> {
         root.insert("hosts", toml_edit::Item::Table(hosts));
     }
 
-    Ok(crate::config::new_config(root))
+    Ok(crate::config::new_config(
+        root,
+        crate::credentials::Credentials::system(),
+    ))
 }
 
 fn read_config_file(filename: &str) -> Result<String> {
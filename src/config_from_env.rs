@@ -1,22 +1,63 @@
-use std::env;
+use std::{collections::HashMap, env};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use thiserror::Error;
 
-use crate::cmd_auth::parse_host;
-use crate::config_file::get_env_var;
+use crate::{cmd_auth::parse_host, config::Config, config_file::get_env_var};
 
 const OXIDE_HOST: &str = "OXIDE_HOST";
 const OXIDE_TOKEN: &str = "OXIDE_TOKEN";
+const OXIDE_TOKEN_FILE: &str = "OXIDE_TOKEN_FILE";
+const OXIDE_ORG: &str = "OXIDE_ORG";
+const OXIDE_ORGANIZATION: &str = "OXIDE_ORGANIZATION";
+const OXIDE_PROFILE: &str = "OXIDE_PROFILE";
 
 pub struct EnvConfig<'a> {
     pub config: &'a mut (dyn crate::config::Config + 'a),
+
+    /// Values overridden for this command, like with `--profile`, by key, with the flag
+    /// they come from.
+    overrides: HashMap<String, (String, String)>,
 }
 
 impl EnvConfig<'_> {
     pub fn inherit_env(config: &mut dyn crate::config::Config) -> EnvConfig {
-        EnvConfig { config }
+        EnvConfig {
+            config,
+            overrides: HashMap::new(),
+        }
     }
+
+    /// Return a value from the profile in use, with the profile as the source.
+    ///
+    /// The organization and project of a profile only apply to the host of the
+    /// profile, since they would not exist on another host.
+    fn profile_value(&self, hostname: &str, key: &str) -> Result<Option<(String, String)>> {
+        let (profile, _) = self.current_profile_with_source()?;
+        if profile.is_empty() || !crate::config::PROFILE_KEYS.contains(&key) {
+            return Ok(None);
+        }
+
+        let host = self.config.get_profile(&profile, "host")?;
+        if !hostname.is_empty() && !host.is_empty() && parse_host(&host)?.to_string() != hostname {
+            return Ok(None);
+        }
+
+        let value = self.config.get_profile(&profile, key)?;
+        if value.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some((value, format!("profile {}", profile))))
+    }
+}
+
+/// Read a token from a file, like a secret mounted into a container.
+pub fn read_token_file(path: &str) -> Result<String> {
+    let token =
+        std::fs::read_to_string(path).map_err(|err| anyhow!("could not read the token from {}: {}", path, err))?;
+
+    Ok(token.trim().to_string())
 }
 
 #[derive(Error, Debug)]
@@ -35,12 +76,32 @@ impl crate::config::Config for EnvConfig<'_> {
     }
 
     fn get_with_source(&self, hostname: &str, key: &str) -> Result<(String, String)> {
+        if let Some(value) = self.overrides.get(key) {
+            return Ok(value.clone());
+        }
+
         // If they are asking specifically for the token, return the value.
         if key == "token" {
             let token = get_env_var(OXIDE_TOKEN);
             if !token.is_empty() {
                 return Ok((token, OXIDE_TOKEN.to_string()));
             }
+
+            // The file is read every time, so a rotated token is picked up. It comes from
+            // `OXIDE_TOKEN_FILE`, or from `oxide auth login --token-file`.
+            let token_file = self.get(hostname, "token_file").unwrap_or_default();
+            if !token_file.is_empty() {
+                return Ok((read_token_file(&token_file)?, token_file));
+            }
+        } else if key == "organization" {
+            // The organization has always been read from `OXIDE_ORG`, so keep that name
+            // first, but take the `OXIDE_ORGANIZATION` any other key would get as well.
+            for var in [OXIDE_ORG, OXIDE_ORGANIZATION] {
+                let org = get_env_var(var);
+                if !org.is_empty() {
+                    return Ok((org, var.to_string()));
+                }
+            }
         } else {
             let var = format!("OXIDE_{}", heck::AsShoutySnakeCase(key));
             let val = get_env_var(&var);
@@ -49,10 +110,25 @@ impl crate::config::Config for EnvConfig<'_> {
             }
         }
 
+        if let Some(value) = self.profile_value(hostname, key)? {
+            return Ok(value);
+        }
+
+        // Use the user from `OXIDE_USER`, if set, rather than the one logged into the host.
+        if !hostname.is_empty() && crate::config::ACCOUNT_KEYS.contains(&key) {
+            let user = self.get(hostname, "user").unwrap_or_default();
+            return self.config.get_user_with_source(hostname, &user, key);
+        }
+
         self.config.get_with_source(hostname, key)
     }
 
     fn set(&mut self, hostname: &str, key: &str, value: &str) -> Result<()> {
+        if !hostname.is_empty() && crate::config::ACCOUNT_KEYS.contains(&key) {
+            let user = self.get(hostname, "user").unwrap_or_default();
+            return self.config.set_user(hostname, &user, key, value);
+        }
+
         self.config.set(hostname, key, value)
     }
 
@@ -73,6 +149,9 @@ impl crate::config::Config for EnvConfig<'_> {
         if let Ok(host) = env::var(OXIDE_HOST) {
             let host = parse_host(&host)?;
             Ok((host.to_string(), OXIDE_HOST.to_string()))
+        } else if let Some((host, source)) = self.profile_value("", "host")? {
+            let host = parse_host(&host)?;
+            Ok((host.to_string(), source))
         } else {
             self.config.default_host_with_source()
         }
@@ -90,6 +169,47 @@ impl crate::config::Config for EnvConfig<'_> {
         self.config.expand_alias(args)
     }
 
+    fn profiles(&self) -> Result<Vec<String>> {
+        self.config.profiles()
+    }
+
+    fn get_profile(&self, profile: &str, key: &str) -> Result<String> {
+        self.config.get_profile(profile, key)
+    }
+
+    fn set_profile(&mut self, profile: &str, key: &str, value: &str) -> Result<()> {
+        self.config.set_profile(profile, key, value)
+    }
+
+    fn unset_profile(&mut self, profile: &str) -> Result<()> {
+        self.config.unset_profile(profile)
+    }
+
+    fn current_profile_with_source(&self) -> Result<(String, String)> {
+        if let Some(profile) = self.overrides.get("profile") {
+            return Ok(profile.clone());
+        }
+
+        let profile = get_env_var(OXIDE_PROFILE);
+        if !profile.is_empty() {
+            return Ok((profile, OXIDE_PROFILE.to_string()));
+        }
+
+        self.config.current_profile_with_source()
+    }
+
+    fn users(&self, hostname: &str) -> Result<Vec<String>> {
+        self.config.users(hostname)
+    }
+
+    fn get_user_with_source(&self, hostname: &str, user: &str, key: &str) -> Result<(String, String)> {
+        self.config.get_user_with_source(hostname, user, key)
+    }
+
+    fn set_user(&mut self, hostname: &str, user: &str, key: &str, value: &str) -> Result<()> {
+        self.config.set_user(hostname, user, key, value)
+    }
+
     fn check_writable(&self, hostname: &str, key: &str) -> Result<()> {
         // If they are asking specifically for the token, return the value.
         if key == "token" {
@@ -97,6 +217,11 @@ impl crate::config::Config for EnvConfig<'_> {
             if !token.is_empty() {
                 return Err(ReadOnlyEnvVarError::Variable(OXIDE_TOKEN.to_string()).into());
             }
+
+            let token_file = get_env_var(OXIDE_TOKEN_FILE);
+            if !token_file.is_empty() {
+                return Err(ReadOnlyEnvVarError::Variable(OXIDE_TOKEN_FILE.to_string()).into());
+            }
         }
 
         self.config.check_writable(hostname, key)
@@ -113,4 +238,10 @@ impl crate::config::Config for EnvConfig<'_> {
     fn hosts_to_string(&self) -> Result<String> {
         self.config.hosts_to_string()
     }
+
+    fn set_override(&mut self, key: &str, value: &str, source: &str) -> Result<()> {
+        self.overrides
+            .insert(key.to_string(), (value.to_string(), source.to_string()));
+        Ok(())
+    }
 }
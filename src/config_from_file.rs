@@ -6,6 +6,7 @@ use crate::config_alias::AliasConfig;
 #[derive(Debug, Clone)]
 pub struct FileConfig {
     pub map: crate::config_map::ConfigMap,
+    pub credentials: crate::credentials::Credentials,
 }
 
 #[derive(Debug, Clone)]
@@ -47,6 +48,33 @@ impl FileConfig {
         }
     }
 
+    fn get_profiles_table(&self) -> Result<toml_edit::Table> {
+        match self.map.find_entry("profiles") {
+            Ok(profiles) => match profiles.as_table() {
+                Some(p) => Ok(p.clone()),
+                None => Err(anyhow!("profiles is not a table")),
+            },
+            Err(e) => {
+                if e.to_string().contains("not found") {
+                    return Ok(toml_edit::Table::new());
+                }
+
+                return Err(anyhow!("Error reading profiles table: {}", e));
+            }
+        }
+    }
+
+    fn get_profile_config(&self, profile: &str) -> Result<crate::config_map::ConfigMap> {
+        match self.get_profiles_table()?.get(profile) {
+            Some(toml_edit::Item::Table(t)) => Ok(crate::config_map::ConfigMap { root: t.clone() }),
+            _ => Err(anyhow!(
+                "profile `{}` not found. Create it with `oxide profile create {}`.",
+                profile,
+                profile
+            )),
+        }
+    }
+
     fn get_host_entries(&self) -> Result<Vec<HostConfig>> {
         let mut host_configs = Vec::new();
 
@@ -80,7 +108,12 @@ impl FileConfig {
             }
         }
 
-        Err(anyhow!("host `{}` not found. Try authenticating with `oxide auth login` or be sure you are using both env vars: `OXIDE_HOST` and `OXIDE_TOKEN`.", hostname))
+        Err(crate::errors::AuthError(format!(
+            "host `{}` not found. Try authenticating with `oxide auth login` or be sure you are using both env vars: \
+             `OXIDE_HOST` and `OXIDE_TOKEN`.",
+            hostname
+        ))
+        .into())
     }
 
     fn make_host_config(&self, hostname: &str) -> Result<HostConfig> {
@@ -97,6 +130,150 @@ impl FileConfig {
 
         Ok(host_config)
     }
+
+    fn set_host_value(&mut self, hostname: &str, key: &str, value: &str) -> Result<()> {
+        let mut host_config = match self.get_host_config(hostname) {
+            Ok(host_config) => host_config,
+            Err(_) => {
+                // Likely the host doesn't exist, so create it.
+                self.make_host_config(hostname)?
+            }
+        };
+
+        host_config.map.set_string_value(key, value)?;
+
+        // Get our hosts table.
+        let mut hosts_table = self.get_hosts_table()?;
+
+        hosts_table.insert(hostname, toml_edit::Item::Table(host_config.map.root.clone()));
+
+        // Reset the hosts.
+        self.map.root.insert("hosts", toml_edit::Item::Table(hosts_table));
+
+        Ok(())
+    }
+
+    /// Returns the table that holds the token of a user of a host, with the name it is
+    /// stored under. Users are stored in the `users` table of the host. The user of a
+    /// host logged into before a host could have several users has its token stored in
+    /// the host itself, under an empty name.
+    fn get_account(&self, hostname: &str, user: &str) -> Result<(String, crate::config_map::ConfigMap)> {
+        let host_config = self.get_host_config(hostname)?;
+
+        let users = host_config.map.root.get("users");
+        if !user.is_empty() {
+            if let Some(toml_edit::Item::Table(t)) = users.and_then(|users| users.get(user)) {
+                return Ok((user.to_string(), crate::config_map::ConfigMap { root: t.clone() }));
+            }
+        }
+
+        let host_user = host_config.map.get_string_value("user").unwrap_or_default();
+        if user.is_empty() || (user == host_user && has_token(&host_config.map)) {
+            return Ok((String::new(), host_config.map));
+        }
+
+        Ok((
+            user.to_string(),
+            crate::config_map::ConfigMap {
+                root: toml_edit::Table::new(),
+            },
+        ))
+    }
+
+    /// Move the account stored in the host itself to the `users` table of the host, under
+    /// the user it belongs to, before another user is logged into. This keeps the user
+    /// logged in, so it can be switched back to.
+    fn move_host_account(&mut self, hostname: &str, user: &str) -> Result<()> {
+        let host_config = match self.get_host_config(hostname) {
+            Ok(host_config) => host_config.map,
+            Err(_) => return Ok(()),
+        };
+
+        let previous_user = host_config.get_string_value("user").unwrap_or_default();
+        if previous_user.is_empty() || previous_user == user || !has_token(&host_config) {
+            return Ok(());
+        }
+
+        let (_, mut host_account) = self.get_account(hostname, "")?;
+        let storage = token_storage(&host_account);
+
+        let mut account = crate::config_map::ConfigMap {
+            root: toml_edit::Table::new(),
+        };
+        for key in crate::config::ACCOUNT_KEYS {
+            if SECRET_KEYS.contains(&key) && !storage.is_empty() && storage != "insecure" {
+                // Keep the secret in the same store, under the name of the user.
+                let store = self.credentials.store(&storage)?;
+                if let Ok(value) = store.get(&credential_key(hostname, "", key)) {
+                    store.set(&credential_key(hostname, &previous_user, key), &value)?;
+                    store.delete(&credential_key(hostname, "", key))?;
+                }
+            } else if let Ok(value) = host_account.get_string_value(key) {
+                account.set_string_value(key, &value)?;
+            }
+
+            host_account.remove_entry(key)?;
+        }
+
+        self.set_account(hostname, "", host_account)?;
+        self.set_account(hostname, &previous_user, account)
+    }
+
+    fn set_account(&mut self, hostname: &str, user: &str, account: crate::config_map::ConfigMap) -> Result<()> {
+        let host_config = if user.is_empty() {
+            account
+        } else {
+            let mut host_config = self.get_host_config(hostname)?.map;
+
+            let mut users = match host_config.root.get("users") {
+                Some(toml_edit::Item::Table(t)) => t.clone(),
+                _ => toml_edit::Table::new(),
+            };
+            // Users are written as `[host.users.<name>]`, not as an empty `[host.users]` table.
+            users.set_implicit(true);
+            users.insert(user, toml_edit::Item::Table(account.root));
+            host_config.root.insert("users", toml_edit::Item::Table(users));
+
+            host_config
+        };
+
+        let mut hosts_table = self.get_hosts_table()?;
+        hosts_table.insert(hostname, toml_edit::Item::Table(host_config.root));
+        self.map.root.insert("hosts", toml_edit::Item::Table(hosts_table));
+
+        Ok(())
+    }
+}
+
+/// The keys of a host that are stored in a credential store.
+const SECRET_KEYS: [&str; 2] = ["token", "refresh_token"];
+
+/// Returns if the host itself holds a token, from before a host could have several
+/// users.
+fn has_token(host_config: &crate::config_map::ConfigMap) -> bool {
+    host_config.find_entry("token").is_ok() || host_config.find_entry("token_storage").is_ok()
+}
+
+/// Returns where the token of an account is stored: the name of a credential store,
+/// `insecure` for the hosts file, or an empty string if it was never set.
+fn token_storage(account: &crate::config_map::ConfigMap) -> String {
+    account.get_string_value("token_storage").unwrap_or_default()
+}
+
+/// The name a secret is stored under in a credential store. The token is stored under
+/// the hostname and user, other secrets under the hostname and user followed by the key.
+fn credential_key(hostname: &str, user: &str, key: &str) -> String {
+    let account = if user.is_empty() {
+        hostname.to_string()
+    } else {
+        format!("{} {}", hostname, user)
+    };
+
+    if key == "token" {
+        account
+    } else {
+        format!("{} {}", account, key)
+    }
 }
 
 impl crate::config::Config for FileConfig {
@@ -117,6 +294,11 @@ impl crate::config::Config for FileConfig {
 
         let host_config = self.get_host_config(hostname)?;
 
+        if crate::config::ACCOUNT_KEYS.contains(&key) {
+            let user = host_config.map.get_string_value("user").unwrap_or_default();
+            return self.get_user_with_source(hostname, &user, key);
+        }
+
         let value = host_config.map.get_string_value(key)?;
 
         Ok((value, hosts_source))
@@ -127,25 +309,96 @@ impl crate::config::Config for FileConfig {
             return self.map.set_string_value(key, value);
         }
 
-        let mut host_config = match self.get_host_config(hostname) {
-            Ok(host_config) => host_config,
-            Err(_) => {
-                // Likely the host doesn't exist, so create it.
-                self.make_host_config(hostname)?
+        if crate::config::ACCOUNT_KEYS.contains(&key) {
+            let user = self.get(hostname, "user").unwrap_or_default();
+            return self.set_user(hostname, &user, key, value);
+        }
+
+        if key == "user" {
+            self.move_host_account(hostname, value)?;
+        }
+
+        self.set_host_value(hostname, key, value)
+    }
+
+    fn users(&self, hostname: &str) -> Result<Vec<String>> {
+        let host_config = self.get_host_config(hostname)?;
+
+        let mut users = Vec::new();
+        if has_token(&host_config.map) {
+            let user = host_config.map.get_string_value("user").unwrap_or_default();
+            if !user.is_empty() {
+                users.push(user);
             }
-        };
+        }
 
-        host_config.map.set_string_value(key, value)?;
+        if let Some(toml_edit::Item::Table(t)) = host_config.map.root.get("users") {
+            for (user, _) in t.iter() {
+                if !users.iter().any(|u| u == user) {
+                    users.push(user.to_string());
+                }
+            }
+        }
 
-        // Get our hosts table.
-        let mut hosts_table = self.get_hosts_table()?;
+        Ok(users)
+    }
 
-        hosts_table.insert(hostname, toml_edit::Item::Table(host_config.map.root.clone()));
+    fn get_user_with_source(&self, hostname: &str, user: &str, key: &str) -> Result<(String, String)> {
+        let (user, account) = self.get_account(hostname, user)?;
 
-        // Reset the hosts.
-        self.map.root.insert("hosts", toml_edit::Item::Table(hosts_table));
+        if SECRET_KEYS.contains(&key) {
+            let storage = token_storage(&account);
+            if !storage.is_empty() && storage != "insecure" {
+                let store = self.credentials.store(&storage)?;
+                return Ok((store.get(&credential_key(hostname, &user, key))?, store.location()?));
+            }
+        }
 
-        Ok(())
+        if SECRET_KEYS.contains(&key) && account.root.get(key).is_none() {
+            return Err(crate::errors::AuthError(format!("no {} for {}", key, hostname)).into());
+        }
+
+        let value = account.get_string_value(key)?;
+
+        Ok((value, crate::config_file::hosts_file()?))
+    }
+
+    fn set_user(&mut self, hostname: &str, user: &str, key: &str, value: &str) -> Result<()> {
+        if self.get_host_config(hostname).is_err() {
+            // The host doesn't exist, so create it.
+            self.set_account(
+                hostname,
+                "",
+                crate::config_map::ConfigMap {
+                    root: toml_edit::Table::new(),
+                },
+            )?;
+        }
+
+        let (user, mut account) = self.get_account(hostname, user)?;
+
+        let storage = token_storage(&account);
+        if !SECRET_KEYS.contains(&key) || storage == "insecure" {
+            account.set_string_value(key, value)?;
+            return self.set_account(hostname, &user, account);
+        }
+
+        // Store the secret in a credential store, unless the user logged in with
+        // `--insecure-storage`. The token goes in the first credential store that works,
+        // and the other secrets go in the same store as the token.
+        if key == "token" || storage.is_empty() {
+            let storage = self.credentials.set(&credential_key(hostname, &user, key), value)?;
+            account.set_string_value("token_storage", &storage)?;
+        } else {
+            self.credentials
+                .store(&storage)?
+                .set(&credential_key(hostname, &user, key), value)?;
+        }
+
+        // Remove the secret from the hosts file, in case it was stored there before.
+        account.remove_entry(key)?;
+
+        self.set_account(hostname, &user, account)
     }
 
     fn unset_host(&mut self, hostname: &str) -> Result<()> {
@@ -153,6 +406,23 @@ impl crate::config::Config for FileConfig {
             return Ok(());
         }
 
+        // Remove the secrets of every user from their credential store.
+        let mut users = self.users(hostname).unwrap_or_default();
+        users.push(String::new());
+        for user in users {
+            let (user, account) = match self.get_account(hostname, &user) {
+                Ok(account) => account,
+                Err(_) => continue,
+            };
+            let storage = token_storage(&account);
+            if !storage.is_empty() && storage != "insecure" {
+                let store = self.credentials.store(&storage)?;
+                for key in SECRET_KEYS {
+                    store.delete(&credential_key(hostname, &user, key))?;
+                }
+            }
+        }
+
         let mut hosts_table = self.get_hosts_table()?;
 
         // Remove the host from the table.
@@ -186,7 +456,10 @@ impl crate::config::Config for FileConfig {
         let hosts = self.hosts()?;
 
         if hosts.is_empty() {
-            return Err(anyhow!("No hosts found. Try logging in with `oxide auth login`."));
+            return Err(crate::errors::AuthError(
+                "No hosts found. Try logging in with `oxide auth login`.".to_string(),
+            )
+            .into());
         }
 
         let hosts_source = crate::config_file::hosts_file()?;
@@ -297,6 +570,68 @@ impl crate::config::Config for FileConfig {
         Ok((new_args, is_shell))
     }
 
+    fn profiles(&self) -> Result<Vec<String>> {
+        let profiles_table = self.get_profiles_table()?;
+
+        Ok(profiles_table.iter().map(|(profile, _)| profile.to_string()).collect())
+    }
+
+    fn get_profile(&self, profile: &str, key: &str) -> Result<String> {
+        let profile_config = self.get_profile_config(profile)?;
+
+        // A profile does not have to set every key.
+        Ok(profile_config.get_string_value(key).unwrap_or_default())
+    }
+
+    fn set_profile(&mut self, profile: &str, key: &str, value: &str) -> Result<()> {
+        let mut profile_config = self
+            .get_profile_config(profile)
+            .unwrap_or_else(|_| crate::config_map::ConfigMap {
+                root: toml_edit::Table::new(),
+            });
+
+        profile_config.set_string_value(key, value)?;
+
+        // Get our profiles table.
+        let mut profiles_table = self.get_profiles_table()?;
+        // Profiles are written as `[profiles.<name>]`, not as an empty `[profiles]` table.
+        profiles_table.set_implicit(true);
+
+        profiles_table.insert(profile, toml_edit::Item::Table(profile_config.root));
+
+        // Reset the profiles.
+        self.map.root.insert("profiles", toml_edit::Item::Table(profiles_table));
+
+        Ok(())
+    }
+
+    fn unset_profile(&mut self, profile: &str) -> Result<()> {
+        // Make sure the profile exists.
+        self.get_profile_config(profile)?;
+
+        let mut profiles_table = self.get_profiles_table()?;
+
+        // Remove the profile from the table.
+        profiles_table.remove_entry(profile);
+
+        // Reset the profiles.
+        self.map.root.insert("profiles", toml_edit::Item::Table(profiles_table));
+
+        // Stop using the profile if it was the current one.
+        if self.map.get_string_value("profile").unwrap_or_default() == profile {
+            self.map.remove_entry("profile")?;
+        }
+
+        Ok(())
+    }
+
+    fn current_profile_with_source(&self) -> Result<(String, String)> {
+        let config_source = crate::config_file::config_file()?;
+        let profile = self.map.get_string_value("profile").unwrap_or_default();
+
+        Ok((profile, config_source))
+    }
+
     fn check_writable(&self, _hostname: &str, _key: &str) -> Result<()> {
         // TODO: check if the config file is writable from the filesystem permissions
         Ok(())
@@ -338,4 +673,9 @@ impl crate::config::Config for FileConfig {
 
         Ok(doc.to_string().trim().to_string())
     }
+
+    fn set_override(&mut self, key: &str, _value: &str, _source: &str) -> Result<()> {
+        // Overrides take precedence over the environment too, so they live on top of it.
+        Err(anyhow!("{} can only be overridden on top of the environment", key))
+    }
 }
@@ -1,9 +1,16 @@
 use std::str::FromStr;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use oauth2::TokenResponse;
 
 use crate::{config::Config, config_file::get_env_var, types::FormatOutput};
 
+/// How long to wait to connect to a host, unless `connect_timeout` is set.
+pub const DEFAULT_CONNECT_TIMEOUT: &str = "10s";
+/// How long to wait for a response from a host, unless `http_timeout` or `--timeout`
+/// is set.
+pub const DEFAULT_HTTP_TIMEOUT: &str = "60s";
+
 pub struct Context<'a> {
     pub config: &'a mut (dyn Config + Send + Sync + 'a),
     pub io: crate::iostreams::IoStreams,
@@ -37,7 +44,30 @@ impl Context<'_> {
 
     /// This function returns an API client for Oxide that is based on the configured
     /// user.
-    pub fn api_client(&self, hostname: &str) -> Result<oxide_api::Client> {
+    pub async fn api_client(&mut self, hostname: &str) -> Result<oxide_api::Client> {
+        let (host, token) = self.host_and_token(hostname).await?;
+
+        self.api_client_with_token(&host, &token)
+    }
+
+    /// Returns an HTTP client for requests to the API that the API client has no method
+    /// for, like the ones of `oxide api`. It sends them as the configured user, with the
+    /// HTTP settings of the host, and retries and records them like the API client does.
+    pub async fn api_http_client(&mut self, hostname: &str) -> Result<ApiHttpClient> {
+        let (host, token) = self.host_and_token(hostname).await?;
+
+        Ok(ApiHttpClient {
+            http: self.http_client_builder(&host)?.build()?,
+            baseurl: base_url(&host),
+            token,
+            retries: self.retries(&host)?,
+            tracer: self.io.tracer().cloned(),
+        })
+    }
+
+    /// Returns the host to use, the default one if `hostname` is empty, and the token
+    /// of the user logged into it. The token is refreshed first if it is about to expire.
+    async fn host_and_token(&mut self, hostname: &str) -> Result<(String, String)> {
         // Use the host passed in if it's set.
         // Otherwise, use the default host.
         let host = if hostname.is_empty() {
@@ -46,22 +76,251 @@ impl Context<'_> {
             hostname.to_string()
         };
 
-        // Change the baseURL to the one we want.
-        let mut baseurl = host.to_string();
-        if !host.starts_with("http://") && !host.starts_with("https://") {
-            baseurl = format!("https://{}", host);
-            if host.starts_with("localhost") {
-                baseurl = format!("http://{}", host)
-            }
-        }
+        self.refresh_expiring_token(&host).await?;
 
         // Get the token for that host.
         let token = self.config.get(&host, "token")?;
 
-        // Create the client.
-        let client = oxide_api::Client::new(&token, &baseurl);
+        // Fail with a clear error rather than with `Unauthenticated` from the API.
+        if let Some(expires_at) = self.token_expires_at(&host)? {
+            if expires_at <= chrono::Utc::now() {
+                bail!(crate::errors::AuthError(format!(
+                    "the token for {} expired {}. Run `oxide auth refresh` or `oxide auth login` to get a new one.",
+                    host,
+                    chrono_humanize::HumanTime::from(expires_at)
+                )));
+            }
+        }
+
+        Ok((host, token))
+    }
+
+    /// Returns an API client for a host that uses the given token, rather than the
+    /// token of the user logged into the host.
+    pub fn api_client_with_token(&self, host: &str, token: &str) -> Result<oxide_api::Client> {
+        // The API client has an HTTP client of its own, so it sends its requests through
+        // a gateway that has the HTTP settings of the host, see `crate::gateway`.
+        let gateway = self.io.gateway().route(
+            &base_url(host),
+            self.http_client_builder(host)?.build()?,
+            self.retries(host)?,
+            self.io.tracer().cloned(),
+        )?;
+
+        Ok(oxide_api::Client::new(token, gateway))
+    }
+
+    /// Returns a builder for HTTP clients to a host, with the HTTP settings of the host:
+    /// the certificate authorities to trust in `ca_cert`, the client certificate in
+    /// `client_cert` and `client_key`, `insecure` to skip verifying the certificate of
+    /// the host, the `proxy`, and the `connect_timeout` and `http_timeout`. A setting that
+    /// is not set for the host comes from the config file.
+    pub fn http_client_builder(&self, host: &str) -> Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(self.timeout_setting(host, "connect_timeout", DEFAULT_CONNECT_TIMEOUT)?)
+            .timeout(self.timeout_setting(host, "http_timeout", DEFAULT_HTTP_TIMEOUT)?);
+
+        // A proxy in the config takes precedence over the one in `HTTPS_PROXY`. Hosts in
+        // `NO_PROXY` are reached directly either way.
+        let mut proxy = self.http_setting(host, "proxy");
+        if proxy.is_empty() {
+            proxy = get_env_var("HTTPS_PROXY");
+        }
+        if proxy.is_empty() {
+            proxy = get_env_var("https_proxy");
+        }
+        if !proxy.is_empty() {
+            builder = builder.proxy(
+                reqwest::Proxy::all(&proxy)
+                    .map_err(|err| anyhow!("invalid proxy {}: {}", proxy, err))?
+                    .no_proxy(reqwest::NoProxy::from_env()),
+            );
+        }
+
+        let ca_cert = self.http_setting(host, "ca_cert");
+        if !ca_cert.is_empty() {
+            // The file can hold several certificates, they are all trusted.
+            let pem = read_pem(&ca_cert)?;
+            builder = builder.add_root_certificate(
+                reqwest::Certificate::from_pem(&pem).map_err(|err| anyhow!("invalid ca_cert {}: {}", ca_cert, err))?,
+            );
+        }
+
+        let client_cert = self.http_setting(host, "client_cert");
+        let client_key = self.http_setting(host, "client_key");
+        match (client_cert.is_empty(), client_key.is_empty()) {
+            (true, true) => {}
+            (false, false) => {
+                let pem = [read_pem(&client_cert)?, read_pem(&client_key)?].concat();
+                builder = builder.identity(
+                    reqwest::Identity::from_pem(&pem)
+                        .map_err(|err| anyhow!("invalid client_cert or client_key for {}: {}", host, err))?,
+                );
+            }
+            _ => bail!("client_cert and client_key must be set together for {}", host),
+        }
+
+        if self.http_setting(host, "insecure") == "true" {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(builder)
+    }
+
+    /// Returns how to retry requests to a host, with the `retries` setting of the host or
+    /// of the config file.
+    pub fn retries(&self, host: &str) -> Result<crate::retry::Retries> {
+        let mut retries = self.http_setting(host, "retries");
+        if retries.is_empty() {
+            retries = crate::retry::DEFAULT_RETRIES.to_string();
+        }
+
+        let retries = retries
+            .parse::<u32>()
+            .map_err(|_| anyhow!("invalid retries `{}`, for example use `3`", retries))?;
+
+        Ok(crate::retry::Retries::new(retries))
+    }
+
+    fn http_setting(&self, host: &str, key: &str) -> String {
+        self.config
+            .get(host, key)
+            .or_else(|_| self.config.get("", key))
+            .unwrap_or_default()
+    }
+
+    fn timeout_setting(&self, host: &str, key: &str, default: &str) -> Result<std::time::Duration> {
+        let mut timeout = self.http_setting(host, key);
+        if timeout.is_empty() {
+            timeout = default.to_string();
+        }
+
+        let timeout = crate::types::Interval::from_str(&timeout).map_err(|err| anyhow!("invalid {}: {}", key, err))?;
 
-        Ok(client)
+        Ok(timeout.0)
+    }
+
+    /// Return when the token for a host expires, if it is known. The expiry of a token
+    /// from `OXIDE_TOKEN` or from a file is never known.
+    pub fn token_expires_at(&self, host: &str) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let (_, source) = self.config.get_with_source(host, "token")?;
+        if source == "OXIDE_TOKEN" || source == self.config.get(host, "token_file").unwrap_or_default() {
+            return Ok(None);
+        }
+
+        let expires_at = self.config.get(host, "token_expires_at").unwrap_or_default();
+        if expires_at.is_empty() {
+            return Ok(None);
+        }
+
+        let expires_at = chrono::DateTime::parse_from_rfc3339(&expires_at)
+            .map_err(|err| anyhow!("invalid token_expires_at for {}: {}", host, err))?;
+
+        Ok(Some(expires_at.with_timezone(&chrono::Utc)))
+    }
+
+    /// Save the token for a user of a host, along with when it expires and the token to
+    /// refresh it with, if they are known.
+    pub fn set_token(
+        &mut self,
+        host: &str,
+        user: &str,
+        token: &str,
+        expires_in: Option<std::time::Duration>,
+        refresh_token: &str,
+    ) -> Result<()> {
+        self.config.set_user(host, user, "token", token)?;
+
+        let expires_at = match expires_in {
+            Some(expires_in) => (chrono::Utc::now() + chrono::Duration::from_std(expires_in)?).to_rfc3339(),
+            None => String::new(),
+        };
+
+        for (key, value) in [
+            ("token_expires_at", expires_at.as_str()),
+            ("refresh_token", refresh_token),
+        ] {
+            // Leave the keys out for hosts that never had them.
+            let previous = self.config.get_user_with_source(host, user, key).unwrap_or_default().0;
+            if !value.is_empty() || !previous.is_empty() {
+                self.config.set_user(host, user, key, value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get a new token for a host with its refresh token, and save it.
+    pub async fn refresh_token(&mut self, host: &str) -> Result<()> {
+        let refresh_token = self.config.get(host, "refresh_token").unwrap_or_default();
+        if refresh_token.is_empty() {
+            bail!(
+                "there is no refresh token for {}. Run `oxide auth login --host {}` to log in again.",
+                host,
+                host
+            );
+        }
+
+        let http = self
+            .http_client_builder(host)?
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let response = crate::cmd_auth::oauth_client(self, host)?
+            .exchange_refresh_token(&oauth2::RefreshToken::new(refresh_token.to_string()))
+            .request_async(|request| crate::cmd_auth::oauth_request(http.clone(), request))
+            .await
+            .map_err(|err| anyhow!("could not refresh the token for {}: {}", host, err))?;
+
+        // The server may keep the same refresh token.
+        let refresh_token = match response.refresh_token() {
+            Some(new_refresh_token) => new_refresh_token.secret().to_string(),
+            None => refresh_token,
+        };
+
+        let user = self.config.get(host, "user").unwrap_or_default();
+        self.set_token(
+            host,
+            &user,
+            response.access_token().secret(),
+            response.expires_in(),
+            &refresh_token,
+        )?;
+        self.config.write()?;
+
+        Ok(())
+    }
+
+    /// Refresh the token for a host if it expires within the hour, so that long running
+    /// jobs don't fail halfway through. If it cannot be refreshed, warn about it instead.
+    pub async fn refresh_expiring_token(&mut self, host: &str) -> Result<()> {
+        let expires_at = match self.token_expires_at(host) {
+            Ok(Some(expires_at)) => expires_at,
+            _ => return Ok(()),
+        };
+
+        if expires_at - chrono::Utc::now() > chrono::Duration::hours(1) {
+            return Ok(());
+        }
+
+        let cs = self.io.color_scheme();
+        if !self.config.get(host, "refresh_token").unwrap_or_default().is_empty() {
+            match self.refresh_token(host).await {
+                Ok(()) => return Ok(()),
+                Err(err) => writeln!(self.io.err_out, "{} {}", cs.warning_icon(), err)?,
+            }
+        }
+
+        if expires_at > chrono::Utc::now() {
+            writeln!(
+                self.io.err_out,
+                "{} The token for {} expires {}. Run `oxide auth login` to get a new one.",
+                cs.warning_icon(),
+                host,
+                chrono_humanize::HumanTime::from(expires_at)
+            )?;
+        }
+
+        Ok(())
     }
 
     /// This function opens a browser that is based on the configured
@@ -111,6 +370,98 @@ impl Context<'_> {
             Ok(FormatOutput::from_str(&value).unwrap_or_default())
         }
     }
+
+    /// Return the value passed for `--organization` or `--project`, falling back to the
+    /// current context set with `oxide context set`, or its environment variable.
+    ///
+    /// This returns an empty string if the value is not set anywhere.
+    pub fn context_value(&self, key: &str, value: &str) -> String {
+        if !value.is_empty() {
+            return value.to_string();
+        }
+
+        // The context is stored per host, so a missing host just means there is no context.
+        let host = self.config.default_host().unwrap_or_default();
+        self.config.get(&host, key).unwrap_or_default()
+    }
+
+    /// Return the organization to use for a command, see `context_value`.
+    pub fn organization(&self, organization: &str) -> Result<String> {
+        let organization = self.context_value("organization", organization);
+        if organization.is_empty() {
+            return Err(crate::errors::InvalidInputError(
+                "-o|--organization required, or set a default with `oxide context set --organization <name>`"
+                    .to_string(),
+            )
+            .into());
+        }
+
+        Ok(organization)
+    }
+
+    /// Return the project to use for a command, see `context_value`.
+    pub fn project(&self, project: &str) -> Result<String> {
+        let project = self.context_value("project", project);
+        if project.is_empty() {
+            return Err(crate::errors::InvalidInputError(
+                "-p|--project required, or set a default with `oxide context set --project <name>`".to_string(),
+            )
+            .into());
+        }
+
+        Ok(project)
+    }
+}
+
+/// Sends requests to the API of a host without the API client, see
+/// `Context::api_http_client`.
+pub struct ApiHttpClient {
+    http: reqwest::Client,
+    baseurl: String,
+    token: String,
+    retries: crate::retry::Retries,
+    tracer: Option<std::sync::Arc<crate::trace::Tracer>>,
+}
+
+impl ApiHttpClient {
+    /// Returns a request to a path of the API, like `/organizations`.
+    pub fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", self.baseurl.trim_end_matches('/'), path))
+            .bearer_auth(&self.token)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+    }
+
+    /// Send a request, retrying it if it fails for a passing reason, see
+    /// `crate::retry::Retries`, and recording it, see `crate::trace`.
+    pub async fn send(&self, req: reqwest::Request) -> Result<reqwest::Response> {
+        let resp = self
+            .retries
+            .send(req.method().is_idempotent(), || {
+                // Bodies are sent as bytes, never streamed, so requests can be cloned.
+                let req = req.try_clone().expect("the body of an API request is streamed");
+                crate::trace::send(self.tracer.as_deref(), &self.http, req)
+            })
+            .await?;
+
+        Ok(resp)
+    }
+}
+
+/// Returns the base URL of the API of a host, like `https://oxide.example.com`.
+fn base_url(host: &str) -> String {
+    if host.starts_with("http://") || host.starts_with("https://") {
+        host.to_string()
+    } else if host.starts_with("localhost") {
+        format!("http://{}", host)
+    } else {
+        format!("https://{}", host)
+    }
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|err| anyhow!("could not read {}: {}", path, err))
 }
 
 #[cfg(test)]
@@ -204,4 +555,242 @@ mod test {
             }
         }
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_token_expiry() {
+        std::env::remove_var("OXIDE_HOST");
+        std::env::remove_var("OXIDE_TOKEN");
+        std::env::remove_var("OXIDE_USER");
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (mut io, _, stderr_path) = crate::iostreams::IoStreams::test();
+        io.set_color_enabled(false);
+        let mut ctx = Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        let host = "https://oxide.example.com/";
+
+        // A token without an expiry, like one from `oxide auth login --with-token`.
+        ctx.set_token(host, "", "MY_TOKEN", None, "").unwrap();
+        assert_eq!(ctx.token_expires_at(host).unwrap(), None);
+        assert!(ctx.config.get(host, "token_expires_at").is_err());
+        assert!(ctx.api_client(host).await.is_ok());
+
+        ctx.set_token(host, "", "MY_TOKEN", Some(std::time::Duration::from_secs(30 * 60)), "")
+            .unwrap();
+        let expires_at = ctx.token_expires_at(host).unwrap().unwrap();
+        assert!(expires_at > chrono::Utc::now() + chrono::Duration::minutes(29));
+
+        // Without a refresh token, a token about to expire is only warned about when a
+        // client for its host is made.
+        assert!(ctx.api_client(host).await.is_ok());
+        let stderr = std::fs::read_to_string(&stderr_path).unwrap();
+        assert!(
+            stderr.starts_with("! The token for https://oxide.example.com/ expires in "),
+            "{}",
+            stderr
+        );
+
+        ctx.config
+            .set(
+                host,
+                "token_expires_at",
+                &(chrono::Utc::now() - chrono::Duration::hours(2)).to_rfc3339(),
+            )
+            .unwrap();
+        let err = ctx.api_client(host).await.err().unwrap();
+        assert_eq!(
+            crate::errors::ErrorReport::new(&err).kind,
+            crate::errors::ErrorKind::Auth
+        );
+        assert_eq!(
+            err.to_string(),
+            "the token for https://oxide.example.com/ expired 2 hours ago. Run `oxide auth refresh` or `oxide auth \
+             login` to get a new one."
+        );
+
+        let err = ctx.refresh_token(host).await.unwrap_err();
+        assert!(err.to_string().starts_with("there is no refresh token"), "{}", err);
+
+        // The expiry of a token from the environment is not known.
+        std::env::set_var("OXIDE_TOKEN", "ENV_TOKEN");
+        assert_eq!(ctx.token_expires_at(host).unwrap(), None);
+        std::env::remove_var("OXIDE_TOKEN");
+    }
+
+    async fn get(ctx: &Context<'_>, host: &str) -> Result<String> {
+        let resp = ctx.http_client_builder(host)?.build()?.get(host).send().await?;
+        Ok(resp.text().await?)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_http_client_tls() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio_rustls::rustls;
+
+        // A certificate authority, and a certificate for localhost signed by it.
+        let mut ca_params = rcgen::CertificateParams::new(vec![]);
+        ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let ca_path = dir.path().join("ca.pem");
+        let ca_path = ca_path.to_str().unwrap();
+        std::fs::write(ca_path, ca.serialize_pem().unwrap()).unwrap();
+
+        let tls_config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![rustls::Certificate(cert.serialize_der_with_signer(&ca).unwrap())],
+                rustls::PrivateKey(cert.serialize_private_key_der()),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(std::sync::Arc::new(tls_config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("https://localhost:{}/", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    // The handshake fails for clients that don't trust the certificate.
+                    if let Ok(mut stream) = acceptor.accept(stream).await {
+                        let mut buf = [0; 1024];
+                        let _ = stream.read(&mut buf).await;
+                        let _ = stream
+                            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                            .await;
+                        let _ = stream.shutdown().await;
+                    }
+                });
+            }
+        });
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, _, _) = crate::iostreams::IoStreams::test();
+        let ctx = Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        // The certificate is signed by a CA the system doesn't know about.
+        assert!(get(&ctx, &host).await.is_err());
+
+        ctx.config.set(&host, "ca_cert", ca_path).unwrap();
+        assert_eq!(get(&ctx, &host).await.unwrap(), "ok");
+
+        // Or the certificate is not verified at all.
+        ctx.config.set(&host, "ca_cert", "").unwrap();
+        ctx.config.set(&host, "insecure", "true").unwrap();
+        assert_eq!(get(&ctx, &host).await.unwrap(), "ok");
+
+        ctx.config.set(&host, "http_timeout", "soon").unwrap();
+        let err = ctx.http_client_builder(&host).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid http_timeout: invalid interval `soon`, for example use `2s`"
+        );
+        ctx.config.set(&host, "http_timeout", "").unwrap();
+
+        ctx.config.set(&host, "client_cert", ca_path).unwrap();
+        let err = ctx.http_client_builder(&host).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("client_cert and client_key must be set together for {}", host)
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_http_client_timeout() {
+        std::env::remove_var("OXIDE_HTTP_TIMEOUT");
+
+        // A host that accepts connections but never answers, like a hung Nexus.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(stream);
+            }
+        });
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, _, _) = crate::iostreams::IoStreams::test();
+        let ctx = Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        ctx.config.set(&host, "http_timeout", "100ms").unwrap();
+        let err = get(&ctx, &host).await.unwrap_err();
+        assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout(), "{}", err);
+
+        // `--timeout` takes precedence over the config.
+        ctx.config.set(&host, "http_timeout", "1h").unwrap();
+        ctx.config.set_override("http_timeout", "100ms", "--timeout").unwrap();
+        let err = get(&ctx, &host).await.unwrap_err();
+        assert!(err.downcast_ref::<reqwest::Error>().unwrap().is_timeout(), "{}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_api_client_proxy() {
+        if !crate::gateway::test::with_dead_http_proxy("context::test::test_api_client_proxy") {
+            return;
+        }
+        std::env::remove_var("OXIDE_HOST");
+        std::env::remove_var("OXIDE_TOKEN");
+
+        // The proxy answers the requests itself.
+        let (proxy, server) = crate::gateway::test::stub_server(vec![crate::gateway::test::MAZE_WAR]).await;
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        let mut ctx = Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+
+        let host = "http://oxide.example.com/";
+        ctx.config.set(host, "token", "MY_TOKEN").unwrap();
+        ctx.config.set(host, "proxy", &proxy).unwrap();
+
+        // A generated command goes through the proxy of the host, even with `HTTP_PROXY`
+        // set, which is only the proxy for hosts without one.
+        let cmd = crate::cmd_org::CmdOrganizationView {
+            organization: "maze-war".to_string(),
+            web: false,
+            watch: false,
+            interval: Default::default(),
+            format: Some(crate::types::FormatOutput::Json),
+        };
+        crate::cmd::Command::run(&cmd, &mut ctx).await.unwrap();
+
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert!(stdout.contains("\"name\": \"maze-war\""), "{}", stdout);
+
+        let request = server.await.unwrap().remove(0);
+        assert!(
+            request.starts_with("GET http://oxide.example.com/organizations/maze-war HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert!(request.contains("authorization: Bearer MY_TOKEN\r\n"), "{}", request);
+    }
 }
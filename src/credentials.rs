@@ -0,0 +1,384 @@
+use std::{num::NonZeroU32, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use ring::rand::SecureRandom;
+use thiserror::Error;
+
+use crate::config_file::get_env_var;
+
+/// The passphrase for the encrypted credentials file.
+pub const OXIDE_PASSPHRASE: &str = "OXIDE_PASSPHRASE";
+
+/// The name tokens are stored under in the system keyring.
+const KEYRING_SERVICE: &str = "oxide";
+
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// None of the credential stores could store a token, like when there is no system
+/// keyring and `OXIDE_PASSPHRASE` is not set. It holds why each store failed.
+#[derive(Error, Debug)]
+#[error(
+    "could not store the token securely ({0}). Set {} to store it in an encrypted file, or log in with \
+     `--insecure-storage` to store it in plaintext.",
+    OXIDE_PASSPHRASE
+)]
+pub struct NoCredentialStoreError(pub String);
+
+/// A place to store the tokens for hosts other than the hosts file.
+pub trait CredentialStore: std::fmt::Debug + Send + Sync {
+    /// Describe where the tokens are stored, for `oxide auth status`.
+    fn location(&self) -> Result<String>;
+    /// Returns the token for a host.
+    fn get(&self, hostname: &str) -> Result<String>;
+    /// Sets the token for a host.
+    fn set(&self, hostname: &str, token: &str) -> Result<()>;
+    /// Remove the token for a host.
+    fn delete(&self, hostname: &str) -> Result<()>;
+}
+
+/// The credential stores, by the name saved as `token_storage` for each host in the
+/// hosts file.
+///
+/// When a token is set, the stores are tried in order and the first one that works
+/// is used. A host with a `token_storage` of `insecure`, set by
+/// `oxide auth login --insecure-storage`, keeps its token in the hosts file.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub stores: Vec<(String, Arc<dyn CredentialStore>)>,
+}
+
+impl Credentials {
+    /// The stores of the system: the system keyring, which is the Secret Service API on
+    /// Linux, and a file encrypted with the passphrase in `OXIDE_PASSPHRASE`.
+    pub fn system() -> Self {
+        Credentials {
+            stores: vec![
+                ("keyring".to_string(), Arc::new(Keyring {})),
+                ("encrypted-file".to_string(), Arc::new(EncryptedFile {})),
+            ],
+        }
+    }
+
+    /// A keyring that keeps tokens in memory, for tests.
+    #[cfg(test)]
+    pub fn mock() -> Self {
+        Credentials {
+            stores: vec![("keyring".to_string(), Arc::new(MockKeyring::default()))],
+        }
+    }
+
+    /// No keyring at all, like in CI or a container, for tests.
+    #[cfg(test)]
+    pub fn unavailable() -> Self {
+        Credentials {
+            stores: vec![("keyring".to_string(), Arc::new(MissingKeyring {}))],
+        }
+    }
+
+    /// Returns the store with the given name.
+    pub fn store(&self, name: &str) -> Result<&dyn CredentialStore> {
+        match self.stores.iter().find(|(n, _)| n == name) {
+            Some((_, store)) => Ok(store.as_ref()),
+            None => Err(anyhow!("unknown token storage `{}`", name)),
+        }
+    }
+
+    /// Set the token for a host in the first store that works, and return the name of
+    /// the store.
+    pub fn set(&self, hostname: &str, token: &str) -> Result<String> {
+        let mut errors = Vec::new();
+        for (name, store) in &self.stores {
+            match store.set(hostname, token) {
+                Ok(()) => return Ok(name.to_string()),
+                Err(err) => errors.push(format!("{}: {}", name, err)),
+            }
+        }
+
+        Err(NoCredentialStoreError(errors.join(", ")).into())
+    }
+}
+
+/// The system keyring.
+#[derive(Debug)]
+pub struct Keyring {}
+
+impl CredentialStore for Keyring {
+    fn location(&self) -> Result<String> {
+        Ok("the system keyring".to_string())
+    }
+
+    fn get(&self, hostname: &str) -> Result<String> {
+        keyring::Entry::new(KEYRING_SERVICE, hostname)
+            .get_password()
+            .map_err(|err| match err {
+                keyring::Error::NoEntry => {
+                    crate::errors::AuthError(format!("no token for {} in the system keyring", hostname)).into()
+                }
+                err => anyhow!(
+                    "could not read the token for {} from the system keyring: {}",
+                    hostname,
+                    err
+                ),
+            })
+    }
+
+    fn set(&self, hostname: &str, token: &str) -> Result<()> {
+        keyring::Entry::new(KEYRING_SERVICE, hostname)
+            .set_password(token)
+            .map_err(|err| anyhow!("{}", err))
+    }
+
+    fn delete(&self, hostname: &str) -> Result<()> {
+        match keyring::Entry::new(KEYRING_SERVICE, hostname).delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(anyhow!("{}", err)),
+        }
+    }
+}
+
+/// A toml file with a token for each host, encrypted with AES-256-GCM and a key
+/// derived from the passphrase in `OXIDE_PASSPHRASE`.
+#[derive(Debug)]
+pub struct EncryptedFile {}
+
+impl EncryptedFile {
+    fn read(&self) -> Result<toml_edit::Document> {
+        let path = crate::config_file::credentials_file()?;
+        if !std::path::Path::new(&path).exists() {
+            return Ok(toml_edit::Document::new());
+        }
+
+        Ok(std::fs::read_to_string(&path)?.parse::<toml_edit::Document>()?)
+    }
+
+    fn passphrase(&self) -> Result<String> {
+        let passphrase = get_env_var(OXIDE_PASSPHRASE);
+        if passphrase.is_empty() {
+            bail!("{} is not set", OXIDE_PASSPHRASE);
+        }
+
+        Ok(passphrase)
+    }
+}
+
+impl CredentialStore for EncryptedFile {
+    fn location(&self) -> Result<String> {
+        crate::config_file::credentials_file()
+    }
+
+    fn get(&self, hostname: &str) -> Result<String> {
+        let doc = self.read()?;
+        let sealed = match doc.get(hostname).and_then(|item| item.as_str()) {
+            Some(sealed) => sealed.to_string(),
+            None => bail!(crate::errors::AuthError(format!(
+                "no token for {} in {}",
+                hostname,
+                self.location()?
+            ))),
+        };
+
+        open(&self.passphrase()?, hostname, &sealed)
+    }
+
+    fn set(&self, hostname: &str, token: &str) -> Result<()> {
+        let sealed = seal(&self.passphrase()?, hostname, token)?;
+
+        let mut doc = self.read()?;
+        doc.insert(hostname, toml_edit::value(sealed));
+
+        crate::config_file::write_config_file(&self.location()?, doc.to_string().trim())
+    }
+
+    fn delete(&self, hostname: &str) -> Result<()> {
+        let mut doc = self.read()?;
+        if doc.remove(hostname).is_none() {
+            return Ok(());
+        }
+
+        crate::config_file::write_config_file(&self.location()?, doc.to_string().trim())
+    }
+}
+
+/// Encrypt a token with a key derived from the passphrase. The host is authenticated
+/// along with the token, so a token cannot be moved to another host in the file.
+fn seal(passphrase: &str, hostname: &str, token: &str) -> Result<String> {
+    let rng = ring::rand::SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| anyhow!("could not generate a salt"))?;
+    rng.fill(&mut nonce)
+        .map_err(|_| anyhow!("could not generate a nonce"))?;
+
+    let mut in_out = token.as_bytes().to_vec();
+    key(passphrase, &salt)?
+        .seal_in_place_append_tag(
+            ring::aead::Nonce::assume_unique_for_key(nonce),
+            ring::aead::Aad::from(hostname.as_bytes()),
+            &mut in_out,
+        )
+        .map_err(|_| anyhow!("could not encrypt the token"))?;
+
+    Ok(data_encoding::BASE64.encode(&[&salt[..], &nonce[..], &in_out[..]].concat()))
+}
+
+fn open(passphrase: &str, hostname: &str, sealed: &str) -> Result<String> {
+    let sealed = data_encoding::BASE64
+        .decode(sealed.as_bytes())
+        .map_err(|_| anyhow!("the token for {} is not valid base64", hostname))?;
+    if sealed.len() < SALT_LEN + NONCE_LEN {
+        bail!("the token for {} is too short", hostname);
+    }
+
+    let (salt, rest) = sealed.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce = ring::aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid nonce"))?;
+
+    let mut in_out = ciphertext.to_vec();
+    let token = key(passphrase, salt)?
+        .open_in_place(nonce, ring::aead::Aad::from(hostname.as_bytes()), &mut in_out)
+        .map_err(|_| {
+            anyhow!(
+                "could not decrypt the token for {}, is {} right?",
+                hostname,
+                OXIDE_PASSPHRASE
+            )
+        })?;
+
+    Ok(String::from_utf8(token.to_vec())?)
+}
+
+fn key(passphrase: &str, salt: &[u8]) -> Result<ring::aead::LessSafeKey> {
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+
+    let key = ring::aead::UnboundKey::new(&ring::aead::AES_256_GCM, &key).map_err(|_| anyhow!("invalid key"))?;
+
+    Ok(ring::aead::LessSafeKey::new(key))
+}
+
+/// A keyring that keeps tokens in memory.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MockKeyring {
+    tokens: std::sync::Mutex<std::collections::HashMap<String, String>>,
+}
+
+#[cfg(test)]
+impl CredentialStore for MockKeyring {
+    fn location(&self) -> Result<String> {
+        Ok("the system keyring".to_string())
+    }
+
+    fn get(&self, hostname: &str) -> Result<String> {
+        match self.tokens.lock().unwrap().get(hostname) {
+            Some(token) => Ok(token.to_string()),
+            None => Err(crate::errors::AuthError(format!("no token for {} in the system keyring", hostname)).into()),
+        }
+    }
+
+    fn set(&self, hostname: &str, token: &str) -> Result<()> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(hostname.to_string(), token.to_string());
+        Ok(())
+    }
+
+    fn delete(&self, hostname: &str) -> Result<()> {
+        self.tokens.lock().unwrap().remove(hostname);
+        Ok(())
+    }
+}
+
+/// A keyring that is not there.
+#[cfg(test)]
+#[derive(Debug)]
+pub struct MissingKeyring {}
+
+#[cfg(test)]
+impl CredentialStore for MissingKeyring {
+    fn location(&self) -> Result<String> {
+        Ok("the system keyring".to_string())
+    }
+
+    fn get(&self, _hostname: &str) -> Result<String> {
+        Err(anyhow!("no system keyring found"))
+    }
+
+    fn set(&self, _hostname: &str, _token: &str) -> Result<()> {
+        Err(anyhow!("no system keyring found"))
+    }
+
+    fn delete(&self, _hostname: &str) -> Result<()> {
+        Err(anyhow!("no system keyring found"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let sealed = seal("correct horse", "https://oxide.example.com/", "MY_TOKEN").unwrap();
+        assert!(!sealed.contains("MY_TOKEN"));
+
+        assert_eq!(
+            open("correct horse", "https://oxide.example.com/", &sealed).unwrap(),
+            "MY_TOKEN"
+        );
+
+        // Sealing twice gives different results, since the salt and nonce are random.
+        assert_ne!(
+            seal("correct horse", "https://oxide.example.com/", "MY_TOKEN").unwrap(),
+            sealed
+        );
+
+        let err = open("wrong", "https://oxide.example.com/", &sealed).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "could not decrypt the token for https://oxide.example.com/, is OXIDE_PASSPHRASE right?"
+        );
+
+        // The token cannot be used for another host.
+        assert!(open("correct horse", "https://other.example.com/", &sealed).is_err());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_encrypted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::env::set_var("OXIDE_CONFIG_DIR", dir.path());
+        std::env::remove_var(OXIDE_PASSPHRASE);
+
+        let store = EncryptedFile {};
+        let err = store.set("example.com", "MY_TOKEN").unwrap_err();
+        assert_eq!(err.to_string(), "OXIDE_PASSPHRASE is not set");
+
+        std::env::set_var(OXIDE_PASSPHRASE, "correct horse");
+        store.set("example.com", "MY_TOKEN").unwrap();
+        store.set("oxide.computer", "OTHER_TOKEN").unwrap();
+        assert_eq!(store.get("example.com").unwrap(), "MY_TOKEN");
+        assert_eq!(store.get("oxide.computer").unwrap(), "OTHER_TOKEN");
+
+        let contents = std::fs::read_to_string(dir.path().join("credentials.toml")).unwrap();
+        assert!(!contents.contains("MY_TOKEN"));
+
+        store.delete("example.com").unwrap();
+        assert!(store.get("example.com").is_err());
+        assert_eq!(store.get("oxide.computer").unwrap(), "OTHER_TOKEN");
+
+        std::env::remove_var(OXIDE_PASSPHRASE);
+        std::env::remove_var("OXIDE_CONFIG_DIR");
+    }
+}
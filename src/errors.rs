@@ -0,0 +1,365 @@
+use std::io::Write;
+
+use anyhow::Result;
+use thiserror::Error;
+
+/// The kind of an error, so scripts can tell errors apart by the exit code of oxide or
+/// by the `kind` of the json error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Any other error.
+    Other,
+    /// An invalid request, value, or flag.
+    InvalidInput,
+    /// Not being logged in, or not being allowed to do something.
+    Auth,
+    /// A resource that does not exist.
+    NotFound,
+    /// A resource that already exists.
+    AlreadyExists,
+    /// An error of the host, like an internal error or being unavailable.
+    Server,
+    /// Not being able to reach the host, or the connection failing.
+    Network,
+}
+
+impl ErrorKind {
+    /// The exit code of oxide for the kind of error. These are stable, so scripts can
+    /// depend on them. An invalid flag is 2, like with the errors of the flag parser.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::InvalidInput => 2,
+            ErrorKind::Auth => 3,
+            ErrorKind::NotFound => 4,
+            ErrorKind::AlreadyExists => 5,
+            ErrorKind::Server => 6,
+            ErrorKind::Network => 7,
+        }
+    }
+
+    fn from_status(status: reqwest::StatusCode, error_code: &str) -> Self {
+        match error_code {
+            "ObjectNotFound" => return ErrorKind::NotFound,
+            "ObjectAlreadyExists" => return ErrorKind::AlreadyExists,
+            _ => {}
+        }
+
+        match status.as_u16() {
+            401 | 403 => ErrorKind::Auth,
+            404 => ErrorKind::NotFound,
+            409 => ErrorKind::AlreadyExists,
+            400..=499 => ErrorKind::InvalidInput,
+            500..=599 => ErrorKind::Server,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// An error of not being logged in, or of not having a token that can be used, which
+/// is of `ErrorKind::Auth`.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct AuthError(pub String);
+
+/// An error of a missing or invalid flag or value, which is of `ErrorKind::InvalidInput`.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct InvalidInputError(pub String);
+
+/// An error response of the API to a request made with `oxide api`.
+#[derive(Debug, Clone)]
+pub struct HttpError {
+    pub status: reqwest::StatusCode,
+    pub error_code: String,
+    pub message: String,
+    pub request_id: String,
+}
+
+impl HttpError {
+    /// Read the error out of a response. The API returns errors as json like
+    /// `{"request_id": "...", "error_code": "ObjectNotFound", "message": "..."}`.
+    pub fn from_body(status: reqwest::StatusCode, headers: &reqwest::header::HeaderMap, body: &[u8]) -> Self {
+        let header_request_id = headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        let body: serde_json::Value = serde_json::from_slice(body).unwrap_or_default();
+        let field = |key: &str| body.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+        let mut request_id = field("request_id");
+        if request_id.is_empty() {
+            request_id = header_request_id;
+        }
+
+        HttpError {
+            status,
+            error_code: field("error_code"),
+            message: field("message"),
+            request_id,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.message.is_empty() {
+            write!(f, "{}", self.status)
+        } else {
+            write!(f, "{}: {}", self.status, self.message)
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// The request ID of an error response of the API client, which drops it, as context
+/// of its error. See `crate::gateway::Gateway::resolve`.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request {}", self.0)
+    }
+}
+
+/// An error as it is printed to standard error as json, with `--format json`:
+/// `{"kind": "not_found", "message": "...", "request_id": "..."}`. The request ID is
+/// null when the error has none.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ErrorReport {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub request_id: Option<String>,
+}
+
+impl ErrorReport {
+    pub fn new(err: &anyhow::Error) -> Self {
+        use oxide_api::types::Error;
+
+        let (kind, message) = match err.downcast_ref::<Error>() {
+            Some(Error::ObjectNotFound { message }) => (ErrorKind::NotFound, message.to_string()),
+            Some(Error::ObjectAlreadyExists { message }) => (ErrorKind::AlreadyExists, message.to_string()),
+            Some(Error::InvalidRequest { message }) | Some(Error::InvalidValue { message }) => {
+                (ErrorKind::InvalidInput, message.to_string())
+            }
+            Some(Error::Unauthenticated { internal_message }) => (ErrorKind::Auth, internal_message.to_string()),
+            Some(Error::Forbidden) => (
+                ErrorKind::Auth,
+                "you are not authorized to perform this action".to_string(),
+            ),
+            Some(Error::InternalError { internal_message }) | Some(Error::ServiceUnavailable { internal_message }) => {
+                (ErrorKind::Server, internal_message.to_string())
+            }
+            Some(Error::MethodNotAllowed { internal_message }) => {
+                (ErrorKind::InvalidInput, internal_message.to_string())
+            }
+            None => (kind(err), err.to_string()),
+        };
+
+        let request_id = err
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<HttpError>())
+            .map(|err| err.request_id.to_string())
+            .or_else(|| err.downcast_ref::<RequestId>().map(|id| id.0.to_string()))
+            .filter(|id| !id.is_empty());
+
+        ErrorReport {
+            kind,
+            message,
+            request_id,
+        }
+    }
+
+    /// Write the error as a line of json to standard error.
+    pub fn write_json(&self, io: &mut crate::iostreams::IoStreams) -> Result<()> {
+        writeln!(io.err_out, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// The kind of an error that did not come from the API client.
+fn kind(err: &anyhow::Error) -> ErrorKind {
+    for cause in err.chain() {
+        if let Some(err) = cause.downcast_ref::<HttpError>() {
+            return ErrorKind::from_status(err.status, &err.error_code);
+        }
+
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return match err.status() {
+                Some(status) => ErrorKind::from_status(status, ""),
+                None if err.is_timeout() || err.is_connect() || err.is_request() || err.is_body() => ErrorKind::Network,
+                None => ErrorKind::Other,
+            };
+        }
+
+        if cause.downcast_ref::<AuthError>().is_some() {
+            return ErrorKind::Auth;
+        }
+
+        if cause.downcast_ref::<InvalidInputError>().is_some()
+            || cause.downcast_ref::<clap::Error>().is_some()
+            || cause.downcast_ref::<crate::api_spec::ValidationError>().is_some()
+        {
+            return ErrorKind::InvalidInput;
+        }
+    }
+
+    ErrorKind::Other
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_error_report() {
+        let tests = vec![
+            (
+                anyhow::anyhow!(oxide_api::types::Error::ObjectNotFound {
+                    message: "not found: organization with name \"maze-war\"".to_string(),
+                }),
+                ErrorKind::NotFound,
+                "not found: organization with name \"maze-war\"",
+                None,
+            ),
+            (
+                anyhow::anyhow!(oxide_api::types::Error::ObjectNotFound {
+                    message: "not found: organization with name \"maze-war\"".to_string(),
+                })
+                .context(RequestId("b5d4e0a4".to_string())),
+                ErrorKind::NotFound,
+                "not found: organization with name \"maze-war\"",
+                Some("b5d4e0a4".to_string()),
+            ),
+            (
+                anyhow::anyhow!(oxide_api::types::Error::Forbidden),
+                ErrorKind::Auth,
+                "you are not authorized to perform this action",
+                None,
+            ),
+            (
+                anyhow::Error::new(HttpError {
+                    status: reqwest::StatusCode::BAD_REQUEST,
+                    error_code: "ObjectAlreadyExists".to_string(),
+                    message: "already exists: organization \"maze-war\"".to_string(),
+                    request_id: "b5d4e0a4".to_string(),
+                }),
+                ErrorKind::AlreadyExists,
+                "400 Bad Request: already exists: organization \"maze-war\"",
+                Some("b5d4e0a4".to_string()),
+            ),
+            (
+                anyhow::Error::new(HttpError {
+                    status: reqwest::StatusCode::SERVICE_UNAVAILABLE,
+                    error_code: "".to_string(),
+                    message: "".to_string(),
+                    request_id: "".to_string(),
+                })
+                .context("could not list organizations"),
+                ErrorKind::Server,
+                "could not list organizations",
+                None,
+            ),
+            (
+                anyhow::anyhow!("nothing to edit"),
+                ErrorKind::Other,
+                "nothing to edit",
+                None,
+            ),
+            (
+                anyhow::Error::new(AuthError("not logged in to any hosts".to_string())).context("could not log out"),
+                ErrorKind::Auth,
+                "could not log out",
+                None,
+            ),
+            (
+                anyhow::Error::new(InvalidInputError("--organization or --project required".to_string())),
+                ErrorKind::InvalidInput,
+                "--organization or --project required",
+                None,
+            ),
+        ];
+
+        for (err, kind, message, request_id) in tests {
+            let report = ErrorReport::new(&err);
+            assert_eq!(
+                report,
+                ErrorReport {
+                    kind,
+                    message: message.to_string(),
+                    request_id,
+                }
+            );
+        }
+
+        // Not being logged in, having no token, and missing flags are typed errors.
+        use crate::config::Config;
+
+        let host = "https://oxide.example.com/";
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut tests = vec![
+            (config.default_host().unwrap_err(), ErrorKind::Auth),
+            (config.get(host, "token").unwrap_err(), ErrorKind::Auth),
+        ];
+        config.set(host, "default", "true").unwrap();
+        tests.push((config.get(host, "token").unwrap_err(), ErrorKind::Auth));
+
+        let ctx = crate::context::Context::new(&mut config);
+        tests.push((ctx.organization("").unwrap_err(), ErrorKind::InvalidInput));
+        tests.push((ctx.project("").unwrap_err(), ErrorKind::InvalidInput));
+
+        for (err, kind) in tests {
+            assert_eq!(ErrorReport::new(&err).kind, kind, "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let codes: Vec<i32> = [
+            ErrorKind::Other,
+            ErrorKind::InvalidInput,
+            ErrorKind::Auth,
+            ErrorKind::NotFound,
+            ErrorKind::AlreadyExists,
+            ErrorKind::Server,
+            ErrorKind::Network,
+        ]
+        .iter()
+        .map(|kind| kind.exit_code())
+        .collect();
+        assert_eq!(codes, vec![1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_http_error_from_body() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-request-id", "a4e0d4b5".parse().unwrap());
+
+        // Without a json body, the request ID comes from the header.
+        let err = HttpError::from_body(reqwest::StatusCode::BAD_GATEWAY, &headers, b"<html>Bad Gateway</html>");
+        assert_eq!(err.to_string(), "502 Bad Gateway");
+        assert_eq!(err.request_id, "a4e0d4b5");
+
+        let body = br#"{"request_id":"b5d4e0a4","error_code":"ObjectNotFound","message":"not found"}"#;
+        let err = anyhow::Error::new(HttpError::from_body(reqwest::StatusCode::NOT_FOUND, &headers, body));
+        assert_eq!(err.to_string(), "404 Not Found: not found");
+
+        let report = ErrorReport::new(&err);
+        assert_eq!(report.kind, ErrorKind::NotFound);
+        assert_eq!(report.request_id, Some("b5d4e0a4".to_string()));
+
+        let (mut io, _, stderr_path) = crate::iostreams::IoStreams::test();
+        report.write_json(&mut io).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(stderr_path).unwrap(),
+            "{\"kind\":\"not_found\",\"message\":\"404 Not Found: not found\",\"request_id\":\"b5d4e0a4\"}\n"
+        );
+    }
+}
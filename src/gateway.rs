@@ -0,0 +1,561 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+
+/// Sends the requests of the API client to their host, with the HTTP settings of the host.
+///
+/// `oxide_api::Client` sends requests with an HTTP client of its own, which has none of
+/// the settings of the host: `ca_cert`, `client_cert` and `client_key`, `insecure`,
+/// `proxy`, and the timeouts. So the API client is pointed at a gateway on localhost
+/// instead, which sends each request on to the host with an HTTP client that has them,
+/// and retries it like `crate::retry::Retries` says. Retrying here rather than around
+/// the calls of the API client means the retries see the status and the `Retry-After`
+/// of the responses, and the method of the requests. Every request sent to the host is
+/// recorded with `--verbose-http` and `--trace-file`, see `crate::trace`.
+///
+/// Every API client gets a route on the gateway, under a random path that only this
+/// process knows. Other processes on the machine cannot send requests through it with
+/// the client certificate of a host, since requests to any other path are refused.
+///
+/// A request that cannot reach the host gets a 502 Bad Gateway, with a reference to the
+/// error in the body. `Gateway::resolve` swaps the error of the API client for the
+/// original one, so it keeps its message and its kind, like being a timeout. The API
+/// client also drops the request IDs of error responses, so the gateway keeps them, and
+/// `Gateway::resolve` attaches the one of an error as a `crate::errors::RequestId`.
+///
+/// The requests to the gateway carry the token of the user in plain HTTP, so they must
+/// not go through `HTTP_PROXY`. `oxide_api::Client` makes its HTTP client itself, and
+/// the only say there is in its proxies is `NO_PROXY`, so the gateway adds its address
+/// to `NO_PROXY` before it hands out a route.
+#[derive(Default)]
+pub struct Gateway {
+    server: Mutex<Option<Arc<Server>>>,
+}
+
+/// The gateway once it is listening.
+struct Server {
+    /// The base URL of the routes, with the random path in it.
+    url: String,
+    /// The random path, as requests to the routes start with it.
+    prefix: String,
+    routes: Mutex<HashMap<u64, Route>>,
+    /// The errors of the requests that could not reach their host, by reference.
+    failures: Mutex<HashMap<u64, reqwest::Error>>,
+    /// The request IDs of the error responses of the hosts, by the error the API client
+    /// makes of them.
+    request_ids: Mutex<HashMap<String, String>>,
+    next_id: AtomicU64,
+}
+
+/// Where the requests to a route go.
+struct Route {
+    upstream: String,
+    http: reqwest::Client,
+    retries: crate::retry::Retries,
+    tracer: Option<Arc<crate::trace::Tracer>>,
+}
+
+/// The reference to an error in the body of a 502 Bad Gateway from the gateway.
+static FAILURE: Lazy<regex::Regex> = Lazy::new(|| regex::Regex::new(r"\[oxide gateway error (\d+)\]").unwrap());
+
+impl Gateway {
+    /// Returns the base URL for an API client that sends its requests to `upstream`
+    /// with `http`, retrying them with `retries` and recording them with `tracer`. The
+    /// gateway starts listening with the first route.
+    pub fn route(
+        &self,
+        upstream: &str,
+        http: reqwest::Client,
+        retries: crate::retry::Retries,
+        tracer: Option<Arc<crate::trace::Tracer>>,
+    ) -> Result<String> {
+        let server = self.server()?;
+        bypass_proxies();
+        let id = server.next_id.fetch_add(1, Ordering::Relaxed);
+        server.routes.lock().unwrap().insert(
+            id,
+            Route {
+                upstream: upstream.trim_end_matches('/').to_string(),
+                http,
+                retries,
+                tracer,
+            },
+        );
+
+        Ok(format!("{}{}", server.url, id))
+    }
+
+    /// Swap an error of the API client for the error that kept its request from
+    /// reaching the host, if that is what it is, or attach the request ID to an error
+    /// response. Any other error is returned as it is.
+    pub fn resolve(&self, err: anyhow::Error) -> anyhow::Error {
+        let server = match &*self.server.lock().unwrap() {
+            Some(server) => server.clone(),
+            None => return err,
+        };
+
+        if let Some(api_err) = err.downcast_ref::<oxide_api::types::Error>() {
+            let request_id = server.request_ids.lock().unwrap().get(&api_err.to_string()).cloned();
+            return match request_id {
+                Some(request_id) => err.context(crate::errors::RequestId(request_id)),
+                None => err,
+            };
+        }
+
+        let failure = FAILURE
+            .captures(&format!("{:#}", err))
+            .and_then(|captures| captures[1].parse::<u64>().ok())
+            .and_then(|id| server.failures.lock().unwrap().remove(&id));
+
+        match failure {
+            Some(failure) => failure.into(),
+            None => err,
+        }
+    }
+
+    fn server(&self) -> Result<Arc<Server>> {
+        let mut server = self.server.lock().unwrap();
+        if let Some(server) = &*server {
+            return Ok(server.clone());
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let secret = format!("{:032x}", rand::random::<u128>());
+        let started = Arc::new(Server {
+            url: format!("http://{}/{}/", listener.local_addr()?, secret),
+            prefix: format!("/{}/", secret),
+            routes: Default::default(),
+            failures: Default::default(),
+            request_ids: Default::default(),
+            next_id: Default::default(),
+        });
+
+        let state = started.clone();
+        let make_service = hyper::service::make_service_fn(move |_| {
+            let state = state.clone();
+            async move {
+                Ok::<_, std::convert::Infallible>(hyper::service::service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, std::convert::Infallible>(state.forward(req).await) }
+                }))
+            }
+        });
+        tokio::spawn(hyper::Server::from_tcp(listener)?.serve(make_service));
+
+        *server = Some(started.clone());
+        Ok(started)
+    }
+}
+
+impl Server {
+    async fn forward(&self, req: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+        let (parts, body) = req.into_parts();
+        let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+
+        // The path is the random prefix, the route, and then the path on the host.
+        let route = path.strip_prefix(&self.prefix).and_then(|rest| {
+            let end = rest.find(['/', '?']).unwrap_or(rest.len());
+            let id = rest[..end].parse::<u64>().ok()?;
+            let routes = self.routes.lock().unwrap();
+            let route = routes.get(&id)?;
+            Some((
+                format!("{}{}", route.upstream, &rest[end..]),
+                route.http.clone(),
+                route.retries.clone(),
+                route.tracer.clone(),
+            ))
+        });
+        let (url, client, retries, tracer) = match route {
+            Some(route) => route,
+            None => return response(http::StatusCode::NOT_FOUND, Default::default()),
+        };
+
+        let mut headers = parts.headers;
+        headers.remove(http::header::HOST);
+        let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+
+        let result = async {
+            let resp = retries
+                .send(parts.method.is_idempotent(), || {
+                    let req = client
+                        .request(parts.method.clone(), &url)
+                        .headers(headers.clone())
+                        .body(body.clone())
+                        .build();
+                    let (client, tracer) = (&client, tracer.as_deref());
+                    async move { crate::trace::send(tracer, client, req?).await }
+                })
+                .await?;
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            Ok::<_, reqwest::Error>((status, headers, resp.bytes().await?))
+        }
+        .await;
+
+        match result {
+            Ok((status, headers, body)) => {
+                if status.is_client_error() || status.is_server_error() {
+                    self.record_request_id(status, &headers, &body);
+                }
+
+                let mut resp = response(status, body);
+                for (name, value) in &headers {
+                    if name != http::header::TRANSFER_ENCODING && name != http::header::CONNECTION {
+                        resp.headers_mut().append(name, value.clone());
+                    }
+                }
+                resp
+            }
+            Err(err) => {
+                let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                self.failures.lock().unwrap().insert(id, err);
+                response(
+                    http::StatusCode::BAD_GATEWAY,
+                    format!("[oxide gateway error {}]", id).into(),
+                )
+            }
+        }
+    }
+
+    /// Keep the request ID of an error response, by the error the API client makes of it.
+    fn record_request_id(&self, status: http::StatusCode, headers: &http::HeaderMap, body: &[u8]) {
+        let err = crate::errors::HttpError::from_body(status, headers, body);
+        if err.request_id.is_empty() {
+            return;
+        }
+
+        let api_err = oxide_api::types::Error::from(oxide_api::types::ErrorResponse {
+            error_code: err.error_code,
+            message: err.message,
+            request_id: Default::default(),
+        });
+        self.request_ids
+            .lock()
+            .unwrap()
+            .insert(api_err.to_string(), err.request_id);
+    }
+}
+
+/// Add the address of the gateway to `NO_PROXY`, the way reqwest reads it, so that the
+/// HTTP clients made from then on reach the gateway directly.
+fn bypass_proxies() {
+    let no_proxy = std::env::var("NO_PROXY")
+        .or_else(|_| std::env::var("no_proxy"))
+        .unwrap_or_default();
+    if no_proxy.split(',').any(|host| host.trim() == "127.0.0.1") {
+        return;
+    }
+
+    if no_proxy.trim().is_empty() {
+        std::env::set_var("NO_PROXY", "127.0.0.1");
+    } else {
+        std::env::set_var("NO_PROXY", format!("{},127.0.0.1", no_proxy));
+    }
+}
+
+fn response(status: http::StatusCode, body: hyper::body::Bytes) -> hyper::Response<hyper::Body> {
+    let mut resp = hyper::Response::new(hyper::Body::from(body));
+    *resp.status_mut() = status;
+    resp
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// A server that answers each connection with the next response, and returns the
+    /// requests it got, up to their body.
+    pub(crate) async fn stub_server(responses: Vec<&'static str>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = tokio::io::BufReader::new(stream);
+                let mut request = String::new();
+                while stream.read_line(&mut request).await.unwrap() > 2 {}
+                stream.write_all(response.as_bytes()).await.unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        (host, handle)
+    }
+
+    /// The response to `oxide org view maze-war`.
+    pub(crate) const MAZE_WAR: &str = concat!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 122\r\n\r\n",
+        "{\"id\":\"1\",\"name\":\"maze-war\",\"description\":\"\",",
+        "\"time_created\":\"2022-01-01T00:00:00Z\",\"time_modified\":\"2022-01-01T00:00:00Z\"}",
+    );
+
+    /// reqwest reads `HTTP_PROXY` once per process, so a test that needs it set runs
+    /// again in a process of its own, with `HTTP_PROXY` set to an address that nothing
+    /// listens on and only `localhost` in `NO_PROXY`. Returns whether the test should go
+    /// on, as it is in that process.
+    pub(crate) fn with_dead_http_proxy(test: &str) -> bool {
+        if std::env::var("HTTP_PROXY").is_ok() {
+            return true;
+        }
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([test, "--exact", "--nocapture"])
+            .env("HTTP_PROXY", proxy)
+            .env("NO_PROXY", "localhost")
+            .env_remove("no_proxy")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success() && stdout.contains("1 passed"),
+            "{}{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        false
+    }
+
+    #[tokio::test]
+    async fn test_gateway() {
+        let (host, server) = stub_server(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 13\r\n\r\n{\"items\":[]}\n",
+        ])
+        .await;
+
+        let gateway = Gateway::default();
+        let url = gateway
+            .route(&host, reqwest::Client::new(), crate::retry::Retries::new(0), None)
+            .unwrap();
+        assert!(url.starts_with("http://127.0.0.1:"), "{}", url);
+
+        let resp = reqwest::Client::new()
+            .get(format!("{}/organizations?limit=10", url))
+            .bearer_auth("MY_TOKEN")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.text().await.unwrap(), "{\"items\":[]}\n");
+
+        let request = server.await.unwrap().remove(0);
+        assert!(
+            request.starts_with("GET /organizations?limit=10 HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert!(request.contains("authorization: Bearer MY_TOKEN\r\n"), "{}", request);
+
+        // Requests outside of the routes are refused.
+        let base = url.splitn(4, '/').take(3).collect::<Vec<_>>().join("/");
+        for path in ["/organizations", "/0/organizations", "/not-the-secret/0/organizations"] {
+            let resp = reqwest::get(format!("{}{}", base, path)).await.unwrap();
+            assert_eq!(resp.status(), 404, "{}", path);
+        }
+        let (routes, _) = url.rsplit_once('/').unwrap();
+        let resp = reqwest::get(format!("{}/9/organizations", routes)).await.unwrap();
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_retries() {
+        let unavailable =
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+        let (host, server) = stub_server(vec![
+            unavailable,
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok",
+            unavailable,
+        ])
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let trace_file = dir.path().join("out.har");
+        let tracer = Arc::new(crate::trace::Tracer::new(
+            false,
+            trace_file.to_str().unwrap(),
+            Box::new(std::io::sink()),
+        ));
+
+        let gateway = Gateway::default();
+        let url = gateway
+            .route(
+                &host,
+                reqwest::Client::new(),
+                crate::retry::Retries::new(3),
+                Some(tracer.clone()),
+            )
+            .unwrap();
+
+        // Requests are retried with the status and `Retry-After` of the responses.
+        let resp = reqwest::get(format!("{}/organizations", url)).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "ok");
+
+        // Unless they are not idempotent.
+        let resp = reqwest::Client::new()
+            .post(format!("{}/organizations", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 503);
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(
+            requests[2].starts_with("POST /organizations HTTP/1.1\r\n"),
+            "{}",
+            requests[2]
+        );
+
+        // Every try is recorded.
+        tracer.finish().unwrap();
+        let har: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&trace_file).unwrap()).unwrap();
+        let statuses: Vec<_> = har["log"]["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["response"]["status"].clone())
+            .collect();
+        assert_eq!(statuses, vec![503, 200, 503]);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_unreachable() {
+        // Nothing listens on this port once the listener is dropped.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let gateway = Gateway::default();
+        let url = gateway
+            .route(&host, reqwest::Client::new(), crate::retry::Retries::new(0), None)
+            .unwrap();
+
+        let err = oxide_api::Client::new("MY_TOKEN", &url)
+            .organizations()
+            .get("maze-war")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("502 Bad Gateway"), "{}", err);
+
+        // The error is the one of the request to the host.
+        let err = gateway.resolve(err);
+        let err = err.downcast_ref::<reqwest::Error>().unwrap();
+        assert!(err.is_connect(), "{}", err);
+        assert_eq!(
+            err.url().map(|url| url.to_string()),
+            Some(format!("{}organizations/maze-war", host))
+        );
+
+        // Other errors are left as they are.
+        let err = gateway.resolve(anyhow::anyhow!("invalid value"));
+        assert_eq!(err.to_string(), "invalid value");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    #[serial_test::serial]
+    async fn test_gateway_http_proxy() {
+        if !with_dead_http_proxy("gateway::test::test_gateway_http_proxy") {
+            return;
+        }
+        std::env::remove_var("OXIDE_HOST");
+        std::env::remove_var("OXIDE_TOKEN");
+
+        let (host, server) = stub_server(vec![MAZE_WAR]).await;
+        // The host itself is reached directly, being in `NO_PROXY`.
+        let host = host.replace("127.0.0.1", "localhost");
+
+        let mut config = crate::config::new_blank_config().unwrap();
+        let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+        let (io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        let mut ctx = crate::context::Context {
+            config: &mut c,
+            io,
+            debug: false,
+        };
+        ctx.config.set(&host, "token", "MY_TOKEN").unwrap();
+
+        // A generated command reaches the gateway without going through the proxy.
+        let cmd = crate::cmd_org::CmdOrganizationView {
+            organization: "maze-war".to_string(),
+            web: false,
+            watch: false,
+            interval: Default::default(),
+            format: Some(crate::types::FormatOutput::Json),
+        };
+        crate::cmd::Command::run(&cmd, &mut ctx).await.unwrap();
+
+        let stdout = std::fs::read_to_string(&stdout_path).unwrap();
+        assert!(stdout.contains("\"name\": \"maze-war\""), "{}", stdout);
+
+        let request = server.await.unwrap().remove(0);
+        assert!(
+            request.starts_with("GET /organizations/maze-war HTTP/1.1\r\n"),
+            "{}",
+            request
+        );
+        assert!(request.contains("authorization: Bearer MY_TOKEN\r\n"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn test_gateway_request_id() {
+        let (host, server) = stub_server(vec![
+            concat!(
+                "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: 114\r\n\r\n",
+                "{\"request_id\":\"b5d4e0a4\",\"error_code\":\"ObjectNotFound\",",
+                "\"message\":\"not found: organization with name \\\"maze-war\\\"\"}",
+            ),
+            concat!(
+                "HTTP/1.1 503 Service Unavailable\r\nx-request-id: a4e0d4b5\r\nContent-Type: application/json\r\n",
+                "Content-Length: 57\r\n\r\n{\"error_code\":\"ServiceUnavailable\",\"message\":\"try again\"}",
+            ),
+        ])
+        .await;
+
+        let gateway = Gateway::default();
+        let url = gateway
+            .route(&host, reqwest::Client::new(), crate::retry::Retries::new(0), None)
+            .unwrap();
+        let client = oxide_api::Client::new("MY_TOKEN", &url);
+
+        // The API client keeps only the error of a response, so the request ID of the
+        // response is attached to the error.
+        let err = gateway.resolve(client.organizations().get("maze-war").await.unwrap_err());
+        let report = crate::errors::ErrorReport::new(&err);
+        assert_eq!(
+            report,
+            crate::errors::ErrorReport {
+                kind: crate::errors::ErrorKind::NotFound,
+                message: "not found: organization with name \"maze-war\"".to_string(),
+                request_id: Some("b5d4e0a4".to_string()),
+            }
+        );
+
+        // Or the one in the header, when the body has none.
+        let err = gateway.resolve(client.organizations().get("maze-war").await.unwrap_err());
+        let report = crate::errors::ErrorReport::new(&err);
+        assert_eq!(report.kind, crate::errors::ErrorKind::Server);
+        assert_eq!(report.request_id, Some("a4e0d4b5".to_string()));
+
+        server.await.unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
 use terminal_size::{terminal_size, Height, Width};
 
 const DEFAULT_WIDTH: i32 = 80;
@@ -27,6 +28,14 @@ pub struct IoStreams {
 
     never_prompt: bool,
 
+    columns: Vec<String>,
+    no_headers: bool,
+    query: Option<crate::query::Query>,
+    template: Option<crate::template::Template>,
+    json_errors: bool,
+    tracer: Option<std::sync::Arc<crate::trace::Tracer>>,
+    gateway: crate::gateway::Gateway,
+
     pub tmp_file_override: Option<std::fs::File>,
 }
 
@@ -150,6 +159,73 @@ impl IoStreams {
         self.never_prompt = never_prompt;
     }
 
+    /// Select and order the columns of table, csv, and tsv output by their header.
+    pub fn set_columns(&mut self, columns: Vec<String>) {
+        self.columns = columns;
+    }
+
+    /// Leave out the header row of table, csv, and tsv output.
+    pub fn set_no_headers(&mut self, no_headers: bool) {
+        self.no_headers = no_headers;
+    }
+
+    /// Filter json and yaml output with a `--query` expression. An empty query prints
+    /// output as is.
+    pub fn set_query(&mut self, query: &str) -> Result<()> {
+        self.query = if query.is_empty() { None } else { Some(query.parse()?) };
+
+        Ok(())
+    }
+
+    /// Render output with a `--template` instead of the output format. An empty template
+    /// prints output as usual.
+    pub fn set_template(&mut self, template: &str) -> Result<()> {
+        if template.is_empty() {
+            self.template = None;
+            return Ok(());
+        }
+
+        if self.query.is_some() {
+            return Err(anyhow!("--query and --template cannot be used together"));
+        }
+        self.template = Some(template.parse()?);
+
+        Ok(())
+    }
+
+    /// Print errors as json, for commands that print json.
+    pub fn set_json_errors(&mut self, json_errors: bool) {
+        self.json_errors = json_errors;
+    }
+
+    pub fn json_errors(&self) -> bool {
+        self.json_errors
+    }
+
+    /// Record the requests to the API, with `--verbose-http` or `--trace-file`. The
+    /// requests are printed to `err_out`, which the tracer shares from then on.
+    pub fn start_tracing(&mut self, verbose: bool, trace_file: &str) {
+        let err_out = SharedWriter(std::sync::Arc::new(std::sync::Mutex::new(std::mem::replace(
+            &mut self.err_out,
+            Box::new(std::io::sink()),
+        ))));
+        self.err_out = Box::new(err_out.clone());
+        self.tracer = Some(std::sync::Arc::new(crate::trace::Tracer::new(
+            verbose,
+            trace_file,
+            Box::new(err_out),
+        )));
+    }
+
+    pub fn tracer(&self) -> Option<&std::sync::Arc<crate::trace::Tracer>> {
+        self.tracer.as_ref()
+    }
+
+    /// The gateway the API clients send their requests through, see `crate::gateway`.
+    pub fn gateway(&self) -> &crate::gateway::Gateway {
+        &self.gateway
+    }
+
     #[allow(dead_code)]
     /// This returns a handle to a spinner. To stop the spinner, call `.stop()` on it.
     pub fn start_process_indicator(&mut self) -> Option<terminal_spinners::SpinnerHandle> {
@@ -212,9 +288,14 @@ impl IoStreams {
         value: impl IntoIterator<Item = T> + serde::Serialize,
     ) -> Result<()> {
         match format {
+            _ if self.template.is_some() => self.write_output_json(&serde_json::to_value(value)?),
+            crate::types::FormatOutput::Yaml => self.write_output_yaml(&value),
+            // A query works on the json value, so its results are always printed as json.
+            _ if self.query.is_some() => self.write_output_json(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Json => self.write_output_json(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Table => self.write_output_table_for_vec(value),
-            crate::types::FormatOutput::Yaml => self.write_output_yaml(&value),
+            crate::types::FormatOutput::Csv => self.write_output_delimited_for_vec(value, ','),
+            crate::types::FormatOutput::Tsv => self.write_output_delimited_for_vec(value, '\t'),
         }
     }
 
@@ -224,32 +305,77 @@ impl IoStreams {
         value: &T,
     ) -> Result<()> {
         match format {
+            _ if self.template.is_some() => self.write_output_json(&serde_json::to_value(value)?),
+            crate::types::FormatOutput::Yaml => self.write_output_yaml(value),
+            _ if self.query.is_some() => self.write_output_json(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Json => self.write_output_json(&serde_json::to_value(value)?),
             crate::types::FormatOutput::Table => self.write_output_table(value),
-            crate::types::FormatOutput::Yaml => self.write_output_yaml(value),
+            crate::types::FormatOutput::Csv => self.write_output_delimited_for_vec(vec![value], ','),
+            crate::types::FormatOutput::Tsv => self.write_output_delimited_for_vec(vec![value], '\t'),
         }
     }
 
     pub fn write_output_json(&mut self, json: &serde_json::Value) -> Result<()> {
-        if self.color_enabled() {
-            // Print the response body.
-            writeln!(self.out, "{}", colored_json::to_colored_json_auto(json)?)?;
-        } else {
-            // Print the response body.
-            writeln!(self.out, "{}", serde_json::to_string_pretty(json)?)?;
+        if let Some(template) = &self.template {
+            let out = template.render(json, &self.color_scheme())?;
+            write!(self.out, "{}", out)?;
+            return Ok(());
+        }
+
+        let results = match &self.query {
+            Some(query) => query.apply(json)?,
+            None => vec![json.clone()],
+        };
+
+        for result in results {
+            match result {
+                // Print scalars from a query as is, so they can be used in scripts.
+                serde_json::Value::String(s) if self.query.is_some() => writeln!(self.out, "{}", s)?,
+                serde_json::Value::Null | serde_json::Value::Bool(_) | serde_json::Value::Number(_)
+                    if self.query.is_some() =>
+                {
+                    writeln!(self.out, "{}", result)?
+                }
+                // Print the response body.
+                result if self.color_enabled() => {
+                    writeln!(self.out, "{}", colored_json::to_colored_json_auto(&result)?)?
+                }
+                result => writeln!(self.out, "{}", serde_json::to_string_pretty(&result)?)?,
+            }
         }
 
         Ok(())
     }
 
     pub fn write_output_yaml<Y: serde::Serialize>(&mut self, yaml: &Y) -> Result<()> {
+        if self.template.is_some() {
+            return self.write_output_json(&serde_json::to_value(yaml)?);
+        }
+
         // Print the response body.
-        writeln!(self.out, "{}", serde_yaml::to_string(yaml)?)?;
+        match &self.query {
+            Some(query) => {
+                let mut results = query.apply(&serde_json::to_value(yaml)?)?;
+                let value = if results.len() == 1 {
+                    results.remove(0)
+                } else {
+                    serde_json::Value::Array(results)
+                };
+                writeln!(self.out, "{}", serde_yaml::to_string(&value)?)?;
+            }
+            None => writeln!(self.out, "{}", serde_yaml::to_string(yaml)?)?,
+        }
 
         Ok(())
     }
 
     pub fn write_output_table_for_vec<T: tabled::Tabled>(&mut self, value: impl IntoIterator<Item = T>) -> Result<()> {
+        // tabled can only print every field as is, so when the columns, headers, or cells
+        // are changed we render the same style ourselves.
+        if !self.columns.is_empty() || self.no_headers || self.styles_tables() {
+            return self.write_output_table_for_vec_highlighted(value, &[]);
+        }
+
         let table = tabled::Table::new(value).with(tabled::Style::psql()).to_string();
 
         writeln!(self.out, "{}", table)?;
@@ -258,6 +384,10 @@ impl IoStreams {
     }
 
     pub fn write_output_table<T: tabled::Tabled>(&mut self, value: &T) -> Result<()> {
+        if !self.columns.is_empty() || self.styles_tables() {
+            return self.write_output_table_highlighted(value, None);
+        }
+
         let table = tabled::Table::new(vec![value])
             .with(tabled::Rotate::Left)
             .with(
@@ -273,6 +403,152 @@ impl IoStreams {
         Ok(())
     }
 
+    /// Write the values as comma or tab separated rows, with a header row unless
+    /// `--no-headers` was passed.
+    pub fn write_output_delimited_for_vec<T: tabled::Tabled>(
+        &mut self,
+        value: impl IntoIterator<Item = T>,
+        separator: char,
+    ) -> Result<()> {
+        let (headers, rows) = self.select_columns(value)?;
+
+        let escape = if separator == ',' { escape_csv } else { escape_tsv };
+        let headers = if self.no_headers { None } else { Some(headers) };
+        for row in headers.iter().chain(rows.iter()) {
+            let fields: Vec<String> = row.iter().map(|f| escape(&strip_ansi(f))).collect();
+            writeln!(self.out, "{}", fields.join(&separator.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the headers and rows for the values, limited to and ordered by the columns
+    /// passed with `--columns`.
+    fn select_columns<T: tabled::Tabled>(
+        &self,
+        value: impl IntoIterator<Item = T>,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+        let headers = T::headers();
+        let indexes = self.column_indexes(&headers)?;
+
+        let select = |fields: Vec<String>| -> Vec<String> { indexes.iter().map(|i| fields[*i].clone()).collect() };
+
+        Ok((select(headers), value.into_iter().map(|v| select(v.fields())).collect()))
+    }
+
+    /// Return the indexes of the columns passed with `--columns`, or of every column if
+    /// there are none. Columns are matched against the headers case-insensitively.
+    fn column_indexes(&self, headers: &[String]) -> Result<Vec<usize>> {
+        if self.columns.is_empty() {
+            return Ok((0..headers.len()).collect());
+        }
+
+        self.columns
+            .iter()
+            .map(|column| {
+                headers
+                    .iter()
+                    .position(|h| h.eq_ignore_ascii_case(column.trim()))
+                    .ok_or_else(|| anyhow!("unknown column `{}`, available columns: {}", column, headers.join(", ")))
+            })
+            .collect()
+    }
+
+    /// Whether tables are styled, with colored states and, on a terminal, relative
+    /// timestamps. Without color, like with `NO_COLOR`, tables are printed as they are.
+    fn styles_tables(&self) -> bool {
+        self.color_enabled()
+    }
+
+    /// Style a table cell in the column with the given header: well-known states are
+    /// colored, and timestamps are shown relative to now on a terminal.
+    fn style_cell(&self, header: &str, cell: &str) -> String {
+        if header.starts_with("time_") && self.is_stdout_tty() {
+            if let Some(time) = parse_time(cell) {
+                return chrono_humanize::HumanTime::from(time).to_string();
+            }
+        }
+
+        if !STATE_COLUMNS.contains(&header) {
+            return cell.to_string();
+        }
+
+        // Some states carry data, like the instance of an attached disk.
+        let state = cell
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let cs = self.color_scheme();
+        match state.as_str() {
+            "running" | "attached" | "succeeded" | "enabled" => cs.green(cell),
+            "creating" | "starting" | "stopping" | "rebooting" | "migrating" | "repairing" | "attaching"
+            | "detaching" => cs.yellow(cell),
+            "failed" | "faulted" | "destroyed" => cs.red(cell),
+            "stopped" | "detached" | "disabled" => cs.gray(cell),
+            _ => cell.to_string(),
+        }
+    }
+
+    /// Whether output in the format is printed as a table, that is it is not replaced by
+    /// `--query` or `--template`.
+    pub fn is_table_output(&self, format: &crate::types::FormatOutput) -> bool {
+        *format == crate::types::FormatOutput::Table && self.query.is_none() && self.template.is_none()
+    }
+
+    /// Write a table like `write_output_table_for_vec`, with the rows for which `highlight`
+    /// is true in color. This shows what changed with `--watch`.
+    pub fn write_output_table_for_vec_highlighted<T: tabled::Tabled>(
+        &mut self,
+        value: impl IntoIterator<Item = T>,
+        highlight: &[bool],
+    ) -> Result<()> {
+        let cs = self.color_scheme();
+        let (headers, mut rows) = self.select_columns(value)?;
+        for row in rows.iter_mut() {
+            for (cell, header) in row.iter_mut().zip(&headers) {
+                *cell = self.style_cell(header, cell);
+            }
+        }
+        for (row, _) in rows.iter_mut().zip(highlight).filter(|(_, h)| **h) {
+            highlight_cells(row, &cs);
+        }
+
+        let table = render_psql(if self.no_headers { None } else { Some(&headers) }, &rows);
+        writeln!(self.out, "{}", table)?;
+
+        Ok(())
+    }
+
+    /// Write a value like `write_output_table`, with the fields that differ from `previous`,
+    /// the fields of the value last time, in color.
+    pub fn write_output_table_highlighted<T: tabled::Tabled>(
+        &mut self,
+        value: &T,
+        previous: Option<&[String]>,
+    ) -> Result<()> {
+        let cs = self.color_scheme();
+        let headers = T::headers();
+        let fields = value.fields();
+
+        let rows: Vec<Vec<String>> = self
+            .column_indexes(&headers)?
+            .into_iter()
+            .map(|i| {
+                let mut row = vec![headers[i].clone(), self.style_cell(&headers[i], &fields[i])];
+                if previous.map(|p| p.get(i) != Some(&fields[i])).unwrap_or(false) {
+                    highlight_cells(&mut row, &cs);
+                }
+                row
+            })
+            .collect();
+
+        writeln!(self.out, "{}", render_psql(None, &rows))?;
+
+        Ok(())
+    }
+
     pub fn system() -> Self {
         let stdout_is_tty = atty::is(atty::Stream::Stdout);
         let stderr_is_tty = atty::is(atty::Stream::Stderr);
@@ -320,6 +596,15 @@ impl IoStreams {
             tty_size,
 
             never_prompt: false,
+
+            columns: Default::default(),
+            no_headers: false,
+            query: None,
+            template: None,
+            json_errors: false,
+            tracer: None,
+            gateway: Default::default(),
+
             tmp_file_override: None,
         };
 
@@ -354,6 +639,20 @@ impl IoStreams {
     }
 }
 
+/// A writer that several owners write to in turn, like `err_out` and the tracer.
+#[derive(Clone)]
+struct SharedWriter(std::sync::Arc<std::sync::Mutex<Box<dyn std::io::Write + Send + Sync>>>);
+
+impl std::io::Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
 #[cfg(test)]
 fn test_tty_size() -> Result<(i32, i32)> {
     Err(anyhow::anyhow!("tty_size not implemented in tests"))
@@ -369,9 +668,118 @@ fn tty_size() -> Result<(i32, i32)> {
     }
 }
 
+/// Render rows in the same style as `tabled::Style::psql()`. Cells may span multiple
+/// lines and contain color codes.
+fn render_psql(headers: Option<&Vec<String>>, rows: &[Vec<String>]) -> String {
+    let columns = headers
+        .map(|h| h.len())
+        .unwrap_or_else(|| rows.iter().map(|r| r.len()).max().unwrap_or_default());
+
+    let width = |cell: &str| {
+        strip_ansi(cell)
+            .lines()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or_default()
+    };
+    let mut widths = vec![0; columns];
+    for row in headers.into_iter().chain(rows.iter()) {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(width(cell));
+        }
+    }
+
+    let render_row = |row: &Vec<String>| -> Vec<String> {
+        let cells: Vec<Vec<&str>> = row.iter().map(|c| c.lines().collect()).collect();
+        let height = cells.iter().map(|c| c.len()).max().unwrap_or_default().max(1);
+        (0..height)
+            .map(|line| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        let text = c.get(line).copied().unwrap_or_default();
+                        let pad = widths[i] - strip_ansi(text).chars().count();
+                        format!(" {}{} ", text, " ".repeat(pad))
+                    })
+                    .collect::<Vec<String>>()
+                    .join("|")
+            })
+            .collect()
+    };
+
+    let mut lines = Vec::new();
+    if let Some(headers) = headers {
+        lines.extend(render_row(headers));
+        lines.push(
+            widths
+                .iter()
+                .map(|w| "-".repeat(w + 2))
+                .collect::<Vec<String>>()
+                .join("+"),
+        );
+    }
+    for row in rows {
+        lines.extend(render_row(row));
+    }
+
+    lines.join("\n")
+}
+
+/// The headers of the columns that hold states, like `InstanceState`, `DiskState`,
+/// `SagaState`, and `VpcFirewallRuleStatus`.
+const STATE_COLUMNS: &[&str] = &["run_state", "state", "status"];
+
+/// Parse a timestamp as it is displayed in tables, or as RFC 3339.
+fn parse_time(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(time.with_timezone(&chrono::Utc));
+    }
+
+    let time = chrono::NaiveDateTime::parse_from_str(s.strip_suffix(" UTC")?, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+    Some(chrono::TimeZone::from_utc_datetime(&chrono::Utc, &time))
+}
+
+/// Color every line of the cells, so multi-line cells are colored without bleeding into
+/// the table borders.
+fn highlight_cells(row: &mut [String], cs: &crate::colors::ColorScheme) {
+    for cell in row.iter_mut() {
+        *cell = cell
+            .lines()
+            .map(|l| cs.yellow(&strip_ansi(l)))
+            .collect::<Vec<String>>()
+            .join("\n");
+    }
+}
+
+/// Remove color codes, they have no place in csv or tsv and take up no width in a table.
+pub fn strip_ansi(s: &str) -> String {
+    static ANSI: Lazy<regex::Regex> = Lazy::new(|| regex::Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+    ANSI.replace_all(s, "").to_string()
+}
+
+/// Quote a csv field if it contains a separator, quote, or newline.
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape the characters that would break a tsv row.
+fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
+    use tabled::Tabled;
 
     use super::*;
 
@@ -442,4 +850,140 @@ mod test {
             assert_eq!(width, t.want_width, "test {}", t.name);
         }
     }
+
+    #[derive(serde::Serialize, Tabled)]
+    struct Row {
+        name: String,
+        id: String,
+        description: String,
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row {
+                name: "web".to_string(),
+                id: "1".to_string(),
+                description: "serves \"www\", mostly".to_string(),
+            },
+            Row {
+                name: "db".to_string(),
+                id: "2".to_string(),
+                description: "tab\there".to_string(),
+            },
+        ]
+    }
+
+    fn output(format: &str, columns: &[&str], no_headers: bool) -> Result<String> {
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_columns(columns.iter().map(|c| c.to_string()).collect());
+        io.set_no_headers(no_headers);
+
+        io.write_output_for_vec(&format.parse()?, rows())?;
+
+        Ok(std::fs::read_to_string(stdout_path)?)
+    }
+
+    #[test]
+    fn test_write_output_delimited() {
+        assert_eq!(
+            output("csv", &[], false).unwrap(),
+            "name,id,description\nweb,1,\"serves \"\"www\"\", mostly\"\ndb,2,tab\there\n"
+        );
+        assert_eq!(
+            output("tsv", &[], false).unwrap(),
+            "name\tid\tdescription\nweb\t1\tserves \"www\", mostly\ndb\t2\ttab\\there\n"
+        );
+        assert_eq!(output("csv", &["ID", "name"], true).unwrap(), "1,web\n2,db\n");
+    }
+
+    #[test]
+    fn test_write_output_table_columns() {
+        assert_eq!(
+            output("table", &["id", "name"], false).unwrap(),
+            " id | name \n----+------\n 1  | web  \n 2  | db   \n"
+        );
+        assert_eq!(output("table", &["name"], true).unwrap(), " web \n db  \n");
+
+        let err = output("csv", &["state"], false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown column `state`, available columns: name, id, description"
+        );
+    }
+
+    #[test]
+    fn test_write_output_query() {
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_color_enabled(false);
+        io.set_query(".[] | .id").unwrap();
+        io.write_output_for_vec(&crate::types::FormatOutput::Table, rows())
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), "1\n2\n");
+
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_color_enabled(false);
+        io.set_query(".[0] | {").unwrap_err();
+        io.set_query("[0]").unwrap();
+        io.write_output_json(&serde_json::to_value(rows()).unwrap()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(stdout_path).unwrap(),
+            "{\n  \"description\": \"serves \\\"www\\\", mostly\",\n  \"id\": \"1\",\n  \"name\": \"web\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_write_output_template() {
+        let (mut io, stdout_path, _) = IoStreams::test();
+        io.set_color_enabled(false);
+        io.set_template("{{range .}}{{tablerow .id .name}}{{end}}").unwrap();
+        io.write_output_for_vec(&crate::types::FormatOutput::Yaml, rows())
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(stdout_path).unwrap(), "1  web\n2  db\n");
+
+        let (mut io, _, _) = IoStreams::test();
+        io.set_query(".").unwrap();
+        let err = io.set_template("{{.}}").unwrap_err();
+        assert_eq!(err.to_string(), "--query and --template cannot be used together");
+    }
+
+    #[test]
+    fn test_style_cell() {
+        let (mut io, _, _) = IoStreams::test();
+        io.set_stdout_tty(true);
+        io.set_color_enabled(true);
+        let cs = io.color_scheme();
+
+        assert_eq!(io.style_cell("run_state", "running"), cs.green("running"));
+        assert_eq!(io.style_cell("state", "attached(abc)"), cs.green("attached(abc)"));
+        assert_eq!(io.style_cell("run_state", "starting"), cs.yellow("starting"));
+        assert_eq!(io.style_cell("state", "faulted"), cs.red("faulted"));
+        assert_eq!(io.style_cell("status", "disabled"), cs.gray("disabled"));
+        assert_eq!(io.style_cell("name", "running"), "running");
+
+        let time = chrono::Utc::now() - chrono::Duration::minutes(3);
+        assert_eq!(io.style_cell("time_created", &time.to_string()), "3 minutes ago");
+        assert_eq!(io.style_cell("time_created", &time.to_rfc3339()), "3 minutes ago");
+        assert_eq!(io.style_cell("time_created", ""), "");
+
+        // Without color, tables are not styled even on a terminal.
+        io.set_color_enabled(false);
+        assert!(!io.styles_tables());
+
+        // Without a terminal or color, cells are left alone.
+        io.set_stdout_tty(false);
+        io.set_color_enabled(false);
+        assert_eq!(io.style_cell("run_state", "running"), "running");
+        assert_eq!(io.style_cell("time_created", &time.to_string()), time.to_string());
+    }
+
+    #[test]
+    fn test_render_psql_multiline() {
+        let headers = vec!["name".to_string(), "notes".to_string()];
+        let rows = vec![vec!["web".to_string(), "one\ntwo".to_string()]];
+
+        assert_eq!(
+            render_psql(Some(&headers), &rows),
+            " name | notes \n------+-------\n web  | one   \n      | two   "
+        );
+    }
 }
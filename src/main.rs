@@ -15,10 +15,14 @@ pub mod cmd_auth;
 pub mod cmd_completion;
 /// The config command.
 pub mod cmd_config;
+/// The context command.
+pub mod cmd_context;
 /// The disk command.
 pub mod cmd_disk;
 /// The generate command.
 pub mod cmd_generate;
+/// The hardware command.
+pub mod cmd_hardware;
 /// The image command.
 pub mod cmd_image;
 /// The image global subcommand.
@@ -29,6 +33,8 @@ pub mod cmd_instance;
 pub mod cmd_open;
 /// The organization command.
 pub mod cmd_org;
+/// The profile command.
+pub mod cmd_profile;
 /// The project command.
 pub mod cmd_project;
 /// The rack command.
@@ -47,6 +53,10 @@ pub mod cmd_snapshot;
 pub mod cmd_ssh_key;
 /// The subnet command.
 pub mod cmd_subnet;
+/// The system command.
+pub mod cmd_system;
+/// The tree command.
+pub mod cmd_tree;
 /// The update command.
 pub mod cmd_update;
 /// The version command.
@@ -60,6 +70,8 @@ mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
+mod api_spec;
+mod bulk;
 mod colors;
 mod config;
 mod config_alias;
@@ -68,21 +80,29 @@ mod config_from_env;
 mod config_from_file;
 mod config_map;
 mod context;
+mod credentials;
 mod docs_man;
 mod docs_markdown;
+mod errors;
+mod gateway;
 mod iostreams;
 mod prompt_ext;
+mod query;
+mod retry;
+mod template;
+mod trace;
 mod types;
 
 #[cfg(test)]
 mod tests;
 
 mod update;
+mod watch;
 
 use std::io::{Read, Write};
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
 use slog::Drain;
 
 /// Work seamlessly with Oxide from the command line.
@@ -96,9 +116,36 @@ use slog::Drain;
 /// avoids being prompted to authenticate and takes precedence over previously
 /// stored credentials.
 ///
+/// OXIDE_TOKEN_FILE: a file to read the authentication token from, like a secret
+/// mounted for a service account. The file is read by every command, so a rotated token
+/// is picked up. `OXIDE_TOKEN` takes precedence over it.
+///
 /// OXIDE_HOST: specify the Oxide hostname for commands that would otherwise assume
 /// the "api.oxide.computer" host.
 ///
+/// OXIDE_ORG, OXIDE_PROJECT: the organization and project for commands that take
+/// `--organization` and `--project` when those flags are not passed. These take
+/// precedence over the current context set with `oxide context set`. OXIDE_ORGANIZATION
+/// works as well, but OXIDE_ORG takes precedence over it.
+///
+/// OXIDE_PASSPHRASE: the passphrase for the file tokens are stored in when there is no
+/// system keyring to store them in.
+///
+/// OXIDE_PROFILE: the profile to use, see `oxide profile`. This takes precedence over
+/// the profile set with `oxide profile use`.
+///
+/// OXIDE_USER: the user to use on hosts several users are logged into, see
+/// `oxide auth switch`. This takes precedence over the user switched to.
+///
+/// OXIDE_HTTP_TIMEOUT: how long to wait for a response from a host, like `30s`. This
+/// takes precedence over `http_timeout` in the config, see `oxide config`.
+///
+/// OXIDE_RETRIES: how many times to retry a request that could not connect or that the
+/// host could not serve right then. This takes precedence over `retries` in the config.
+///
+/// HTTPS_PROXY, NO_PROXY: the proxy to reach hosts through, and a comma-separated list of
+/// hosts to reach directly. `proxy` in the config takes precedence over `HTTPS_PROXY`.
+///
 /// OXIDE_BROWSER, BROWSER (in order of precedence): the web browser to use for opening
 /// links.
 ///
@@ -130,7 +177,22 @@ use slog::Drain;
 /// The CLI will attempt to open a browser window with which you can login
 /// (via SAML or other IdP method) and type in or verify the user code printed in the terminal.
 /// After a successful login and code verification, a token associated with the logged-in
-/// user will be granted and stored in the config file.
+/// user will be granted and stored in the system keyring, see `oxide auth login`.
+///
+/// Tracing requests
+///
+/// Print every request to the API and its response with `--verbose-http`, or write them
+/// to a HAR file with `--trace-file out.har` to attach to a bug report. The `Authorization`
+/// header and cookies are redacted in both.
+///
+/// Exit codes
+///
+/// oxide exits with 0 on success, and otherwise with a code for the kind of error, so
+/// scripts can tell errors apart: 1 for other errors, 2 for invalid input, 3 for not being
+/// authenticated or allowed, 4 for a resource that was not found, 5 for a resource that
+/// already exists, 6 for an error of the server, and 7 for a network error. Commands that
+/// print json, with `--format json` or the `format` in the config, print errors to standard
+/// error as json too, like `{"kind":"not_found","message":"...","request_id":"..."}`.
 #[derive(Parser, Debug, Clone)]
 #[clap(version = clap::crate_version!(), author = clap::crate_authors!("\n"))]
 struct Opts {
@@ -138,6 +200,64 @@ struct Opts {
     #[clap(short, long, global = true, env)]
     debug: bool,
 
+    /// Select and order the columns of table, csv, and tsv output, for example `name,id`
+    #[clap(long, global = true, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Leave out the header row of table, csv, and tsv output
+    #[clap(long, global = true)]
+    no_headers: bool,
+
+    /// Filter json output with a jq expression, for example `.[].name`
+    #[clap(long, alias = "jq", global = true, default_value = "", hide_default_value = true)]
+    query: String,
+
+    /// Format output with a Go template, for example `{{range .}}{{.name}}{{"\n"}}{{end}}`
+    #[clap(long, global = true, default_value = "", hide_default_value = true)]
+    template: String,
+
+    /// Use a profile for this command, see `oxide profile`
+    #[clap(long, global = true, env = "OXIDE_PROFILE", default_value = "", hide_default_value = true)]
+    profile: String,
+
+    /// Run this command as another user logged into the host, see `oxide auth switch`
+    #[clap(
+        long = "as",
+        global = true,
+        env = "OXIDE_USER",
+        default_value = "",
+        hide_default_value = true
+    )]
+    as_user: String,
+
+    /// Give up on requests that take longer than this, for example `30s`
+    #[clap(
+        long,
+        global = true,
+        env = "OXIDE_HTTP_TIMEOUT",
+        default_value = "",
+        hide_default_value = true
+    )]
+    timeout: String,
+
+    /// Retry requests that fail for a passing reason this many times, for example `5`
+    #[clap(
+        long,
+        global = true,
+        env = "OXIDE_RETRIES",
+        default_value = "",
+        hide_default_value = true
+    )]
+    retries: String,
+
+    /// Print every request to the API and its response, with credentials redacted
+    #[clap(long, global = true)]
+    verbose_http: bool,
+
+    /// Write every request to the API and its response to a HAR file, with credentials redacted
+    #[clap(long, global = true, default_value = "", hide_default_value = true)]
+    trace_file: String,
+
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
@@ -150,9 +270,11 @@ enum SubCommand {
     Auth(cmd_auth::CmdAuth),
     Completion(cmd_completion::CmdCompletion),
     Config(cmd_config::CmdConfig),
+    Context(cmd_context::CmdContext),
     #[clap(alias = "disks")]
     Disk(cmd_disk::CmdDisk),
     Generate(cmd_generate::CmdGenerate),
+    Hardware(cmd_hardware::CmdHardware),
     #[clap(alias = "images")]
     Image(cmd_image::CmdImage),
     #[clap(alias = "instances")]
@@ -161,6 +283,8 @@ enum SubCommand {
     Open(cmd_open::CmdOpen),
     #[clap(alias = "orgs")]
     Org(cmd_org::CmdOrganization),
+    #[clap(alias = "profiles")]
+    Profile(cmd_profile::CmdProfile),
     #[clap(alias = "projects")]
     Project(cmd_project::CmdProject),
     #[clap(alias = "racks")]
@@ -179,6 +303,8 @@ enum SubCommand {
     SshKey(cmd_ssh_key::CmdSSHKey),
     #[clap(alias = "subnets")]
     Subnet(cmd_subnet::CmdSubnet),
+    System(cmd_system::CmdSystem),
+    Tree(cmd_tree::CmdTree),
     Update(cmd_update::CmdUpdate),
     Version(cmd_version::CmdVersion),
     #[clap(alias = "vpcs")]
@@ -206,8 +332,13 @@ async fn main() -> Result<(), ()> {
     handle_update(&mut ctx, update.await.unwrap_or_default(), build_version).unwrap();
 
     if let Err(err) = result {
-        eprintln!("{}", err);
-        std::process::exit(1);
+        let report = crate::errors::ErrorReport::new(&err);
+        if ctx.io.json_errors() {
+            report.write_json(&mut ctx.io).unwrap();
+        } else {
+            eprintln!("{}", err);
+        }
+        std::process::exit(report.kind.exit_code());
     }
 
     std::process::exit(result.unwrap_or(0));
@@ -265,11 +396,29 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
     }
 
     // Parse the command line arguments.
-    let opts: Opts = Opts::parse_from(args);
+    let matches = Opts::command().get_matches_from(args);
+    let opts = Opts::from_arg_matches(&matches)?;
 
     // Set our debug flag.
     ctx.debug = opts.debug;
 
+    // Set how lists are printed.
+    ctx.io.set_columns(opts.columns.clone());
+    ctx.io.set_no_headers(opts.no_headers);
+    ctx.io.set_query(&opts.query)?;
+    ctx.io.set_template(&opts.template)?;
+
+    // Print errors as json for commands that print json, so scripts can parse them.
+    let json_errors = prints_json(ctx, &matches);
+    ctx.io.set_json_errors(json_errors);
+
+    override_config(ctx, &opts, &matches)?;
+
+    // Record the requests to the API, see `crate::trace`.
+    if opts.verbose_http || !opts.trace_file.is_empty() {
+        ctx.io.start_tracing(opts.verbose_http, &opts.trace_file);
+    }
+
     // Setup our logger. This is mainly for debug purposes.
     // And getting debug logs from other libraries we consume, like even Oxide.
     if ctx.debug {
@@ -287,16 +436,24 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
 
     let result = match opts.subcmd {
         SubCommand::Alias(cmd) => run_cmd(&cmd, ctx).await,
-        SubCommand::Api(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Api(mut cmd) => {
+            if let Some(api_matches) = matches.subcommand_matches("api") {
+                cmd.set_field_order(api_matches);
+            }
+            run_cmd(&cmd, ctx).await
+        }
         SubCommand::Auth(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Completion(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Config(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Context(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Disk(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Generate(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Hardware(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Image(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Instance(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Open(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Org(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Profile(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Project(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Rack(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Role(cmd) => run_cmd(&cmd, ctx).await,
@@ -306,11 +463,18 @@ async fn do_main(mut args: Vec<String>, ctx: &mut crate::context::Context<'_>) -
         SubCommand::Snapshot(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::SshKey(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Subnet(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::System(cmd) => run_cmd(&cmd, ctx).await,
+        SubCommand::Tree(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Update(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Version(cmd) => run_cmd(&cmd, ctx).await,
         SubCommand::Vpc(cmd) => run_cmd(&cmd, ctx).await,
     };
 
+    // Write the trace, whether the command succeeded or not.
+    if let Some(tracer) = ctx.io.tracer() {
+        tracer.finish()?;
+    }
+
     result
 }
 
@@ -318,6 +482,13 @@ async fn run_cmd(cmd: &impl crate::cmd::Command, ctx: &mut context::Context<'_>)
     let cs = ctx.io.color_scheme();
 
     if let Err(err) = cmd.run(ctx).await {
+        let err = ctx.io.gateway().resolve(err);
+        let report = crate::errors::ErrorReport::new(&err);
+        if ctx.io.json_errors() {
+            report.write_json(&mut ctx.io)?;
+            return Ok(report.kind.exit_code());
+        }
+
         // If the error was from the API, let's handle it better for each type of error.
         // These are defined here: https://github.com/oxidecomputer/omicron/blob/main/common/src/api/external/error.rs#L28
         match err.downcast_ref::<oxide_api::types::Error>() {
@@ -380,15 +551,114 @@ async fn run_cmd(cmd: &impl crate::cmd::Command, ctx: &mut context::Context<'_>)
                 )?;
             }
             None => {
-                writeln!(ctx.io.err_out, "{}", err)?;
+                // Requests that time out get their own message, since the fix is a
+                // setting rather than the request.
+                match err
+                    .chain()
+                    .find_map(|cause| cause.downcast_ref::<reqwest::Error>())
+                    .filter(|err| err.is_timeout())
+                {
+                    Some(timeout) => {
+                        writeln!(
+                            ctx.io.err_out,
+                            "{} Timed out waiting for {}. Set a longer timeout with `--timeout`, or \
+                             `http_timeout` and `connect_timeout` in the config.",
+                            cs.failure_icon(),
+                            timeout
+                                .url()
+                                .map(|url| url.to_string())
+                                .unwrap_or_else(|| "the host".to_string())
+                        )?;
+                    }
+                    None => {
+                        writeln!(ctx.io.err_out, "{}", err)?;
+                    }
+                }
             }
         }
-        return Ok(1);
+        return Ok(report.kind.exit_code());
     }
 
     Ok(0)
 }
 
+/// Override the config with the global flags that were given, like `--profile`. These
+/// take precedence over their environment variables and the config for this command
+/// only. The environment variables are read by the config itself.
+fn override_config(ctx: &mut context::Context, opts: &Opts, matches: &clap::ArgMatches) -> Result<()> {
+    let from_flag = |id: &str| matches.value_source(id) == Some(clap::ValueSource::CommandLine);
+
+    if !opts.profile.is_empty() {
+        if from_flag("profile") {
+            ctx.config.set_override("profile", &opts.profile, "--profile")?;
+        }
+
+        // Make sure the profile exists.
+        ctx.config.get_profile(&opts.profile, "host")?;
+    }
+
+    if !opts.as_user.is_empty() {
+        if from_flag("as-user") {
+            ctx.config.set_override("user", &opts.as_user, "--as")?;
+        }
+
+        // Make sure the user is logged into the host.
+        if let Ok(host) = ctx.config.default_host() {
+            let users = ctx.config.users(&host).unwrap_or_default();
+            if !users.is_empty() && !users.contains(&opts.as_user) {
+                anyhow::bail!(crate::errors::AuthError(format!(
+                    "{} is not logged into {}, users logged in are: {}",
+                    opts.as_user,
+                    host,
+                    users.join(", ")
+                )));
+            }
+        }
+    }
+
+    if !opts.timeout.is_empty() {
+        opts.timeout
+            .parse::<crate::types::Interval>()
+            .map_err(|err| anyhow::anyhow!("invalid --timeout: {}", err))?;
+        if from_flag("timeout") {
+            ctx.config.set_override("http_timeout", &opts.timeout, "--timeout")?;
+        }
+    }
+
+    if !opts.retries.is_empty() {
+        opts.retries
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("invalid --retries `{}`, for example use `3`", opts.retries))?;
+        if from_flag("retries") {
+            ctx.config.set_override("retries", &opts.retries, "--retries")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether the command prints json, with `--format json` or the `format` in the
+/// config.
+fn prints_json(ctx: &context::Context, matches: &clap::ArgMatches) -> bool {
+    let root = Opts::command();
+    let mut command = &root;
+    let mut matches = matches;
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        match command.find_subcommand(name) {
+            Some(subcommand) => command = subcommand,
+            None => return false,
+        }
+        matches = sub_matches;
+    }
+
+    if !command.get_arguments().any(|arg| arg.get_id() == "format") {
+        return false;
+    }
+
+    let format = matches.value_of("format").and_then(|format| format.parse().ok());
+    matches!(ctx.format(&format), Ok(crate::types::FormatOutput::Json))
+}
+
 fn handle_update(
     ctx: &mut crate::context::Context,
     update: Option<crate::update::ReleaseInfo>,
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, bail, Result};
+
+/// A `--query` expression, a small subset of jq.
+///
+/// A query is a pipeline of stages separated by `|`. Each stage is either the
+/// `length` or `keys` function, or a path made of:
+///
+/// - `.` for the whole value,
+/// - `.name` or `.["name"]` for a field of an object,
+/// - `.[0]` for an item of an array, counting from the end if negative,
+/// - `.[]` for every item of an array or value of an object.
+///
+/// The leading dot may be left out, so `id` and `items[].name` work like they
+/// do in JMESPath.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    stages: Vec<Vec<Step>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Iterate,
+    Length,
+    Keys,
+}
+
+impl std::str::FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(expr: &str) -> Result<Self> {
+        let stages = split_pipeline(expr)
+            .iter()
+            .map(|stage| parse_stage(stage.trim()).map_err(|err| anyhow!("invalid query `{}`: {}", expr, err)))
+            .collect::<Result<Vec<Vec<Step>>>>()?;
+
+        Ok(Query { stages })
+    }
+}
+
+impl Query {
+    /// Apply the query to the value. A query can return any number of values,
+    /// for example one per item when iterating over an array.
+    pub fn apply(&self, value: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+        let mut values = vec![value.clone()];
+        for step in self.stages.iter().flatten() {
+            let mut next = Vec::new();
+            for value in &values {
+                next.extend(apply_step(step, value)?);
+            }
+            values = next;
+        }
+
+        Ok(values)
+    }
+}
+
+/// Split the expression on the `|` that are not in a quoted key.
+fn split_pipeline(expr: &str) -> Vec<String> {
+    let mut stages = vec![String::new()];
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in expr.chars() {
+        match c {
+            '|' if !quoted => {
+                stages.push(String::new());
+                continue;
+            }
+            '"' if !escaped => quoted = !quoted,
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+        stages.last_mut().unwrap().push(c);
+    }
+
+    stages
+}
+
+fn parse_stage(stage: &str) -> Result<Vec<Step>> {
+    match stage {
+        "" => bail!("empty expression"),
+        "length" => return Ok(vec![Step::Length]),
+        "keys" => return Ok(vec![Step::Keys]),
+        _ => {}
+    }
+
+    let chars: Vec<char> = stage.chars().collect();
+    let mut steps = Vec::new();
+    let mut pos = 0;
+
+    // The first field may be written without a dot.
+    if chars[0] == '.' {
+        pos += 1;
+    }
+    if pos < chars.len() && is_ident(chars[pos]) {
+        steps.push(Step::Field(read_ident(&chars, &mut pos)));
+    }
+
+    while pos < chars.len() {
+        match chars[pos] {
+            '.' => {
+                pos += 1;
+                if pos < chars.len() && chars[pos] == '[' {
+                    continue;
+                }
+                let ident = read_ident(&chars, &mut pos);
+                if ident.is_empty() {
+                    bail!("expected a field name after `.`");
+                }
+                steps.push(Step::Field(ident));
+            }
+            '[' => {
+                let end = closing_bracket(&chars[pos..]).ok_or_else(|| anyhow!("missing `]`"))?;
+                let inner: String = chars[pos + 1..pos + end].iter().collect();
+                steps.push(parse_brackets(inner.trim())?);
+                pos += end + 1;
+            }
+            c => bail!("unexpected `{}`", c),
+        }
+    }
+
+    Ok(steps)
+}
+
+/// The position of the `]` that closes the `[` the characters start with, skipping
+/// over quoted keys, which may contain `]` themselves.
+fn closing_bracket(chars: &[char]) -> Option<usize> {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            ']' if !quoted => return Some(i),
+            '"' if !escaped => quoted = !quoted,
+            _ => {}
+        }
+        escaped = *c == '\\' && !escaped;
+    }
+
+    None
+}
+
+fn parse_brackets(inner: &str) -> Result<Step> {
+    if inner.is_empty() {
+        Ok(Step::Iterate)
+    } else if inner.starts_with('"') {
+        let key: String = serde_json::from_str(inner).map_err(|_| anyhow!("invalid key {}", inner))?;
+        Ok(Step::Field(key))
+    } else {
+        let index = inner
+            .parse::<i64>()
+            .map_err(|_| anyhow!("expected an index, a quoted key, or nothing in `[{}]`", inner))?;
+        Ok(Step::Index(index))
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+fn read_ident(chars: &[char], pos: &mut usize) -> String {
+    let start = *pos;
+    while *pos < chars.len() && is_ident(chars[*pos]) {
+        *pos += 1;
+    }
+    chars[start..*pos].iter().collect()
+}
+
+fn apply_step(step: &Step, value: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+    use serde_json::Value;
+
+    let result = match (step, value) {
+        (Step::Field(name), Value::Object(map)) => vec![map.get(name).cloned().unwrap_or(Value::Null)],
+        (Step::Field(_), Value::Null) => vec![Value::Null],
+        (Step::Field(name), value) => bail!("cannot index {} with \"{}\"", type_name(value), name),
+
+        (Step::Index(index), Value::Array(items)) => {
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            let item = usize::try_from(index).ok().and_then(|i| items.get(i));
+            vec![item.cloned().unwrap_or(Value::Null)]
+        }
+        (Step::Index(_), Value::Null) => vec![Value::Null],
+        (Step::Index(index), value) => bail!("cannot index {} with {}", type_name(value), index),
+
+        (Step::Iterate, Value::Array(items)) => items.clone(),
+        (Step::Iterate, Value::Object(map)) => map.values().cloned().collect(),
+        (Step::Iterate, value) => bail!("cannot iterate over {}", type_name(value)),
+
+        (Step::Length, Value::Array(items)) => vec![items.len().into()],
+        (Step::Length, Value::Object(map)) => vec![map.len().into()],
+        (Step::Length, Value::String(s)) => vec![s.chars().count().into()],
+        (Step::Length, Value::Null) => vec![0.into()],
+        (Step::Length, value) => bail!("{} has no length", type_name(value)),
+
+        (Step::Keys, Value::Object(map)) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            vec![keys.into_iter().map(|k| Value::String(k.to_string())).collect()]
+        }
+        (Step::Keys, Value::Array(items)) => vec![(0..items.len()).map(Value::from).collect()],
+        (Step::Keys, value) => bail!("{} has no keys", type_name(value)),
+    };
+
+    Ok(result)
+}
+
+fn type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn query(expr: &str, value: &serde_json::Value) -> Result<Vec<serde_json::Value>> {
+        expr.parse::<Query>()?.apply(value)
+    }
+
+    #[test]
+    fn test_query() {
+        let value = json!({
+            "items": [
+                {"id": "1", "name": "web", "tags": {"a|b": true, "a]b": "x", "a\"]": "y"}},
+                {"id": "2", "name": "db", "tags": {}},
+            ],
+            "next_page": null,
+        });
+
+        let tests = vec![
+            (".", vec![value.clone()]),
+            ("next_page", vec![json!(null)]),
+            (".items[].name", vec![json!("web"), json!("db")]),
+            ("items[].name", vec![json!("web"), json!("db")]),
+            (".items | .[] | .id", vec![json!("1"), json!("2")]),
+            (".items[-1].name", vec![json!("db")]),
+            (".items[5]", vec![json!(null)]),
+            (".items[0].tags[\"a|b\"]", vec![json!(true)]),
+            (".items[0].tags.[\"a|b\"]", vec![json!(true)]),
+            (".items[0].tags[\"a]b\"]", vec![json!("x")]),
+            (".items[0].tags[\"a\\\"]\"]", vec![json!("y")]),
+            (".items | length", vec![json!(2)]),
+            (".items[0] | keys", vec![json!(["id", "name", "tags"])]),
+            (".missing.field", vec![json!(null)]),
+        ];
+
+        for (expr, want) in tests {
+            assert_eq!(query(expr, &value).unwrap(), want, "query {}", expr);
+        }
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let value = json!({"items": [{"name": "web"}]});
+
+        let tests = vec![
+            (".items.name", "cannot index array with \"name\""),
+            (".items[0].name[]", "cannot iterate over string"),
+            (".items[", "invalid query `.items[`: missing `]`"),
+            (".items[\"]", "invalid query `.items[\"]`: missing `]`"),
+            (".items |", "invalid query `.items |`: empty expression"),
+            (".items[x]", "expected an index, a quoted key, or nothing in `[x]`"),
+            ("..", "expected a field name after `.`"),
+        ];
+
+        for (expr, want) in tests {
+            let err = query(expr, &value).unwrap_err();
+            assert!(err.to_string().contains(want), "query {}: {}", expr, err);
+        }
+    }
+}
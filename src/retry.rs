@@ -0,0 +1,271 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// The number of times a request is retried by default.
+pub const DEFAULT_RETRIES: &str = "3";
+
+/// The delay before the first retry. It doubles with every retry after that.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// The longest delay between retries, even when the host asks for a longer one with
+/// `Retry-After`.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Retries requests to the API that failed in a way that is likely to pass on another
+/// try, like while a rack is being upgraded.
+///
+/// Requests that could not connect are retried whatever their method, since they never
+/// reached the host. Idempotent requests, like GET, PUT and DELETE, are also retried
+/// when they got a 503 Service Unavailable or a 429 Too Many Requests, or when they
+/// failed in any other way on the wire, like a reset connection. Other requests, like
+/// POST, may have been acted on, so they are not. Timeouts are never retried.
+///
+/// The delay between retries grows exponentially, with jitter so that many clients
+/// don't retry at once, unless the host sets `Retry-After`. Every retry is logged with
+/// `--debug`.
+#[derive(Debug, Clone)]
+pub struct Retries {
+    retries: u32,
+    base_delay: Duration,
+}
+
+impl Retries {
+    pub fn new(retries: u32) -> Self {
+        Retries {
+            retries,
+            base_delay: BASE_DELAY,
+        }
+    }
+
+    /// Send a request built by `f` until it succeeds or runs out of retries. The last
+    /// response or error is returned.
+    pub async fn send<F, Fut>(&self, idempotent: bool, mut f: F) -> reqwest::Result<reqwest::Response>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Ok(resp) if attempt < self.retries && idempotent && is_retryable_status(resp.status()) => {
+                    attempt += 1;
+                    self.wait(attempt, retry_after(resp.headers()), &resp.status().to_string())
+                        .await;
+                }
+                Ok(resp) => return Ok(resp),
+                Err(err) if attempt < self.retries && is_retryable_error(&err, idempotent) => {
+                    attempt += 1;
+                    self.wait(attempt, None, &err.to_string()).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    async fn wait(&self, attempt: u32, retry_after: Option<Duration>, reason: &str) {
+        let delay = retry_after.unwrap_or_else(|| self.backoff(attempt));
+        log::debug!("retrying in {:?} ({} of {}): {}", delay, attempt, self.retries, reason);
+        tokio::time::sleep(delay).await;
+    }
+
+    /// The delay before a retry: somewhere between half and all of the base delay doubled
+    /// for every retry before it.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(MAX_DELAY);
+        rand::thread_rng().gen_range(delay / 2..=delay)
+    }
+}
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::SERVICE_UNAVAILABLE || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_retryable_error(err: &reqwest::Error, idempotent: bool) -> bool {
+    if err.is_timeout() {
+        return false;
+    }
+
+    err.is_connect() || (idempotent && (err.is_request() || err.is_body()))
+}
+
+/// Parse a `Retry-After` header, either a number of seconds or a date. A host can't
+/// make us wait longer than `MAX_DELAY`.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    let delay = match value.parse::<u64>() {
+        Ok(seconds) => Duration::from_secs(seconds),
+        Err(_) => {
+            let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+            (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default()
+        }
+    };
+
+    Some(delay.min(MAX_DELAY))
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn retries(retries: u32) -> Retries {
+        Retries {
+            retries,
+            base_delay: Duration::from_millis(1),
+        }
+    }
+
+    /// A server that answers each connection with the next response.
+    async fn stub_server(responses: Vec<&'static str>) -> String {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for response in responses {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = tokio::io::BufReader::new(stream);
+                let mut line = String::new();
+                while stream.read_line(&mut line).await.unwrap() > 2 {
+                    line.clear();
+                }
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        host
+    }
+
+    #[tokio::test]
+    async fn test_retries_send() {
+        let host = stub_server(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok",
+        ])
+        .await;
+
+        let http = reqwest::Client::new();
+        let mut attempts = 0;
+        let resp = retries(3)
+            .send(true, || {
+                attempts += 1;
+                http.get(&host).send()
+            })
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), "ok");
+        assert_eq!(attempts, 3);
+
+        // The last response is returned once the retries run out.
+        let host = stub_server(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await;
+        let resp = retries(1).send(true, || http.get(&host).send()).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        // A request that is not idempotent may have been acted on, so it is not retried.
+        let host = stub_server(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+        ])
+        .await;
+        let mut attempts = 0;
+        let resp = retries(3)
+            .send(false, || {
+                attempts += 1;
+                http.post(&host).send()
+            })
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_send_errors() {
+        // Nothing listens on this port once the listener is dropped.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        // A request that could not connect never reached the host, so it is retried
+        // whatever its method.
+        let http = reqwest::Client::new();
+        let mut attempts = 0;
+        let err = retries(2)
+            .send(false, || {
+                attempts += 1;
+                http.post(&host).send()
+            })
+            .await
+            .unwrap_err();
+        assert!(err.is_connect(), "{}", err);
+        assert_eq!(attempts, 3);
+
+        // A host that accepts connections but never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut streams = Vec::new();
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(stream);
+            }
+        });
+
+        // Timeouts are not retried.
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let mut attempts = 0;
+        let err = retries(2)
+            .send(true, || {
+                attempts += 1;
+                http.get(&host).send()
+            })
+            .await
+            .unwrap_err();
+        assert!(err.is_timeout(), "{}", err);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let retries = Retries::new(10);
+        for attempt in 1..=10 {
+            let delay = retries.backoff(attempt);
+            let max = (BASE_DELAY * 2u32.pow(attempt - 1)).min(MAX_DELAY);
+            assert!(delay >= max / 2 && delay <= max, "attempt {}: {:?}", attempt, delay);
+        }
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(reqwest::header::RETRY_AFTER, "5".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(5)));
+
+        // The host can't make us wait too long.
+        headers.insert(reqwest::header::RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(MAX_DELAY));
+
+        // A date in the past means to retry right away.
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+}
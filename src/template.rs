@@ -0,0 +1,570 @@
+use anyhow::{anyhow, bail, Result};
+
+/// A `--template` for output, the subset of Go templates that `gh` documents for its
+/// `--template` flag.
+///
+/// Templates are rendered against the json value of the output. `{{<pipeline>}}`
+/// prints a value, and `{{if <pipeline>}}` and `{{range <pipeline>}}` take an
+/// optional `{{else}}` and end with `{{end}}`. A pipeline is a value or a function
+/// with its arguments, and `|` passes the result on as the last argument of the next
+/// function. Values are fields like `.name` or `.` itself, strings in double quotes or
+/// backticks, and pipelines in parentheses.
+///
+/// Like in Go, `{{range}}` goes over a list or the values of an object by key, and
+/// `{{if}}` is false for false, 0, null, and empty strings, lists, and objects.
+/// Unlike in Go, which prints `<no value>`, a missing field prints nothing.
+///
+/// The functions are these ones of `gh`:
+///
+/// - `color <style> <text>`: color text with one of bold, red, yellow, green,
+///   gray, purple, blue, or cyan,
+/// - `join <sep> <list>`: join the items of a list,
+/// - `tablerow <fields>...`: add a row to a table whose columns are aligned
+///   when it is rendered,
+/// - `tablerender`: render the table so far, this is done at the end of the
+///   template otherwise,
+/// - `timeago <time>`: print an RFC 3339 time relative to now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Action(Pipeline),
+    If(Pipeline, Vec<Node>, Vec<Node>),
+    Range(Pipeline, Vec<Node>, Vec<Node>),
+}
+
+/// Commands separated by `|`, each command gets the result of the previous one as
+/// its last argument.
+type Pipeline = Vec<Vec<Arg>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Arg {
+    /// A path from the current value like `.a.b`.
+    Field(Vec<String>),
+    Literal(String),
+    Pipeline(Pipeline),
+    Function(String),
+}
+
+impl std::str::FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(template: &str) -> Result<Self> {
+        let mut actions = split(template)
+            .map_err(|err| anyhow!("invalid template: {}", err))?
+            .into_iter();
+        let (nodes, end) = parse_nodes(&mut actions).map_err(|err| anyhow!("invalid template: {}", err))?;
+        if let Some(end) = end {
+            bail!("invalid template: unexpected {{{{{}}}}}", end);
+        }
+
+        Ok(Template { nodes })
+    }
+}
+
+impl Template {
+    /// Render the template against the value.
+    pub fn render(&self, value: &serde_json::Value, cs: &crate::colors::ColorScheme) -> Result<String> {
+        let mut state = State {
+            cs,
+            out: String::new(),
+            rows: Vec::new(),
+        };
+
+        state.exec(&self.nodes, value)?;
+        state.render_rows();
+
+        Ok(state.out)
+    }
+}
+
+struct State<'a> {
+    cs: &'a crate::colors::ColorScheme,
+    out: String,
+    rows: Vec<Vec<String>>,
+}
+
+impl State<'_> {
+    fn exec(&mut self, nodes: &[Node], dot: &serde_json::Value) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.out.push_str(text),
+                Node::Action(pipeline) => {
+                    let value = self.eval_pipeline(pipeline, dot)?;
+                    self.out.push_str(&display(&value));
+                }
+                Node::If(pipeline, body, else_body) => {
+                    if is_true(&self.eval_pipeline(pipeline, dot)?) {
+                        self.exec(body, dot)?;
+                    } else {
+                        self.exec(else_body, dot)?;
+                    }
+                }
+                Node::Range(pipeline, body, else_body) => {
+                    let items = match self.eval_pipeline(pipeline, dot)? {
+                        serde_json::Value::Array(items) => items,
+                        serde_json::Value::Object(map) => map.into_iter().map(|(_, value)| value).collect(),
+                        serde_json::Value::Null => vec![],
+                        value => bail!("range can't iterate over {}", display(&value)),
+                    };
+
+                    if items.is_empty() {
+                        self.exec(else_body, dot)?;
+                    }
+                    for item in &items {
+                        self.exec(body, item)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn eval_pipeline(&mut self, pipeline: &Pipeline, dot: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut piped = None;
+        for command in pipeline {
+            let mut args = command[1..]
+                .iter()
+                .map(|arg| self.eval_arg(arg, dot))
+                .collect::<Result<Vec<serde_json::Value>>>()?;
+            args.extend(piped);
+
+            piped = Some(match &command[0] {
+                Arg::Function(name) => self.call(name, args)?,
+                _ if !args.is_empty() => bail!("can't give arguments to a value that is not a function"),
+                arg => self.eval_arg(arg, dot)?,
+            });
+        }
+
+        Ok(piped.unwrap_or_default())
+    }
+
+    fn eval_arg(&mut self, arg: &Arg, dot: &serde_json::Value) -> Result<serde_json::Value> {
+        match arg {
+            Arg::Field(path) => {
+                let mut value = dot;
+                for key in path {
+                    value = match value {
+                        serde_json::Value::Object(map) => map.get(key).unwrap_or(&serde_json::Value::Null),
+                        serde_json::Value::Null => value,
+                        value => bail!("can't read field {} of {}", key, display(value)),
+                    };
+                }
+                Ok(value.clone())
+            }
+            Arg::Literal(text) => Ok(text.as_str().into()),
+            Arg::Pipeline(pipeline) => self.eval_pipeline(pipeline, dot),
+            Arg::Function(name) => self.call(name, vec![]),
+        }
+    }
+
+    fn call(&mut self, name: &str, args: Vec<serde_json::Value>) -> Result<serde_json::Value> {
+        let arity = |n: usize| {
+            if args.len() != n {
+                Err(anyhow!("{} takes {} arguments, got {}", name, n, args.len()))
+            } else {
+                Ok(())
+            }
+        };
+
+        let text = match name {
+            "color" => {
+                arity(2)?;
+                let value = display(&args[1]);
+                match display(&args[0]).as_str() {
+                    "bold" => self.cs.bold(&value),
+                    "red" => self.cs.red(&value),
+                    "yellow" => self.cs.yellow(&value),
+                    "green" => self.cs.green(&value),
+                    "gray" => self.cs.gray(&value),
+                    "purple" => self.cs.purple(&value),
+                    "blue" => self.cs.blue(&value),
+                    "cyan" => self.cs.cyan(&value),
+                    style => bail!("unknown color {}", style),
+                }
+            }
+            "join" => {
+                arity(2)?;
+                match &args[1] {
+                    serde_json::Value::Array(items) => items
+                        .iter()
+                        .map(display)
+                        .collect::<Vec<String>>()
+                        .join(&display(&args[0])),
+                    value => bail!("join needs a list, got {}", display(value)),
+                }
+            }
+            "tablerow" => {
+                self.rows.push(args.iter().map(display).collect());
+                String::new()
+            }
+            "tablerender" => {
+                arity(0)?;
+                self.render_rows();
+                String::new()
+            }
+            "timeago" => {
+                arity(1)?;
+                match &args[0] {
+                    serde_json::Value::Null => String::new(),
+                    value => {
+                        let time = chrono::DateTime::parse_from_rfc3339(&display(value))
+                            .map_err(|err| anyhow!("timeago can't parse {}: {}", display(value), err))?;
+                        chrono_humanize::HumanTime::from(time).to_string()
+                    }
+                }
+            }
+            name => bail!("unknown function {}", name),
+        };
+
+        Ok(text.into())
+    }
+
+    /// Write the rows added with `tablerow`, with their columns aligned.
+    fn render_rows(&mut self) {
+        let width = |cell: &str| crate::iostreams::strip_ansi(cell).chars().count();
+
+        let mut widths = Vec::new();
+        for row in &self.rows {
+            widths.resize(widths.len().max(row.len()), 0);
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(width(cell));
+            }
+        }
+
+        for row in self.rows.drain(..) {
+            let last = row.len().saturating_sub(1);
+            for (i, cell) in row.iter().enumerate() {
+                self.out.push_str(cell);
+                if i < last {
+                    self.out.push_str(&" ".repeat(widths[i] - width(cell) + 2));
+                }
+            }
+            self.out.push('\n');
+        }
+    }
+}
+
+/// Print a value the way a template does: strings as is, nothing for null, and
+/// lists and objects as json.
+fn display(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.to_string(),
+        serde_json::Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Whether a value is true for `{{if}}`: everything but false, zero, null, and empty
+/// strings, lists, and objects.
+fn is_true(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64() != Some(0.0),
+        serde_json::Value::String(s) => !s.is_empty(),
+        serde_json::Value::Array(items) => !items.is_empty(),
+        serde_json::Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// A piece of the template: text, or the words of an action.
+enum Piece {
+    Text(String),
+    Action(Vec<String>),
+}
+
+/// Split the template into text and actions, and the actions into words: strings,
+/// parentheses, `|`, and everything else up to a space.
+fn split(template: &str) -> Result<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            pieces.push(Piece::Text(rest[..start].to_string()));
+        }
+
+        let mut words = Vec::new();
+        let mut chars = rest[start + 2..].char_indices().peekable();
+        rest = loop {
+            let (i, c) = chars.next().ok_or_else(|| anyhow!("unclosed action"))?;
+            let word = match c {
+                '}' if chars.next_if(|(_, c)| *c == '}').is_some() => break &rest[start + i + 4..],
+                c if c.is_whitespace() => continue,
+                '(' | ')' | '|' => c.to_string(),
+                '"' | '`' => {
+                    let mut word = c.to_string();
+                    let mut escaped = false;
+                    loop {
+                        let (_, next) = chars.next().ok_or_else(|| anyhow!("unterminated string"))?;
+                        word.push(next);
+                        if next == c && !escaped {
+                            break word;
+                        }
+                        escaped = c == '"' && next == '\\' && !escaped;
+                    }
+                }
+                c => {
+                    let mut word = c.to_string();
+                    while let Some((_, next)) = chars.next_if(|(_, c)| !c.is_whitespace() && !"()|}".contains(*c)) {
+                        word.push(next);
+                    }
+                    word
+                }
+            };
+            words.push(word);
+        };
+
+        pieces.push(Piece::Action(words));
+    }
+
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest.to_string()));
+    }
+
+    Ok(pieces)
+}
+
+/// Parse nodes until the end of the template or an `{{end}}` or `{{else}}`, which is
+/// returned.
+fn parse_nodes(pieces: &mut impl Iterator<Item = Piece>) -> Result<(Vec<Node>, Option<String>)> {
+    let mut nodes = Vec::new();
+
+    while let Some(piece) = pieces.next() {
+        let words = match piece {
+            Piece::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Piece::Action(words) => words,
+        };
+
+        match words.first().map(String::as_str) {
+            Some(end @ ("end" | "else")) if words.len() == 1 => return Ok((nodes, Some(end.to_string()))),
+            Some(keyword @ ("if" | "range")) => {
+                let pipeline = parse_pipeline(&words[1..])?;
+                let (body, end) = parse_nodes(pieces)?;
+                let else_body = match end.as_deref() {
+                    Some("end") => vec![],
+                    Some("else") => match parse_nodes(pieces)? {
+                        (nodes, Some(end)) if end == "end" => nodes,
+                        _ => bail!("missing {{{{end}}}} for {{{{{}}}}}", keyword),
+                    },
+                    _ => bail!("missing {{{{end}}}} for {{{{{}}}}}", keyword),
+                };
+
+                nodes.push(match keyword {
+                    "if" => Node::If(pipeline, body, else_body),
+                    _ => Node::Range(pipeline, body, else_body),
+                });
+            }
+            _ => nodes.push(Node::Action(parse_pipeline(&words)?)),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_pipeline(words: &[String]) -> Result<Pipeline> {
+    parse_group(&mut words.iter(), false)
+}
+
+/// Parse the commands of a pipeline up to the end of the words, or up to the `)` that
+/// closes it when it is in parentheses.
+fn parse_group<'a>(words: &mut impl Iterator<Item = &'a String>, nested: bool) -> Result<Pipeline> {
+    let mut commands = vec![vec![]];
+    let mut closed = false;
+
+    while let Some(word) = words.next() {
+        let arg = match word.as_str() {
+            ")" if nested => {
+                closed = true;
+                break;
+            }
+            "|" => {
+                commands.push(vec![]);
+                continue;
+            }
+            "(" => Arg::Pipeline(parse_group(words, true)?),
+            _ if word.starts_with('"') => {
+                Arg::Literal(serde_json::from_str(word).map_err(|_| anyhow!("invalid string {}", word))?)
+            }
+            _ if word.starts_with('`') => Arg::Literal(word[1..word.len() - 1].to_string()),
+            _ if word.starts_with('.') => Arg::Field(
+                word.split('.')
+                    .filter(|key| !key.is_empty())
+                    .map(String::from)
+                    .collect(),
+            ),
+            _ if word.starts_with(char::is_alphabetic) && word.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                Arg::Function(word.to_string())
+            }
+            _ => bail!("unexpected {}", word),
+        };
+        commands.last_mut().unwrap().push(arg);
+    }
+
+    if nested && !closed {
+        bail!("missing )");
+    }
+    if commands.iter().any(|command| command.is_empty()) {
+        bail!("missing value");
+    }
+
+    Ok(commands)
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn render(template: &str, value: &serde_json::Value) -> Result<String> {
+        let cs = crate::colors::ColorScheme::new(false, false, false);
+        template.parse::<Template>()?.render(value, &cs)
+    }
+
+    #[test]
+    fn test_template() {
+        let value = json!([
+            {"name": "web", "run_state": "running", "tags": ["a", "b"], "ncpus": 2},
+            {"name": "database", "run_state": "stopped", "tags": [], "ncpus": 0},
+        ]);
+
+        let tests = vec![
+            (
+                "{{range .}}{{.name}}\t{{.run_state}}{{\"\\n\"}}{{end}}",
+                "web\trunning\ndatabase\tstopped\n",
+            ),
+            (
+                "{{range .}}{{tablerow .name .run_state}}{{end}}",
+                "web       running\ndatabase  stopped\n",
+            ),
+            (
+                "{{range .}}{{if .ncpus}}{{.name}} has {{.ncpus}}{{else}}{{.name}} has none{{end}};{{end}}",
+                "web has 2;database has none;",
+            ),
+            ("{{range .}}{{.tags | join \",\"}};{{end}}", "a,b;;"),
+            (
+                "{{range .}}{{range .missing}}x{{else}}empty{{end}}{{end}}",
+                "emptyempty",
+            ),
+            ("{{range .}}{{color \"red\" `raw\\n`}}{{end}}", "raw\\nraw\\n"),
+        ];
+
+        for (template, want) in tests {
+            assert_eq!(render(template, &value).unwrap(), want, "template {}", template);
+        }
+    }
+
+    /// The examples of `gh help formatting`, without the functions oxide does not have,
+    /// render the way they do with `gh`.
+    #[test]
+    fn test_template_gh() {
+        let issues = json!([
+            {"number": 123, "title": "An exciting issue", "labels": ["bug", "help wanted"]},
+            {"number": 124, "title": "A boring issue", "labels": []},
+        ]);
+        let tests = vec![
+            (
+                "{{range .}}{{tablerow (.number | color \"green\") .title}}{{end}}",
+                "123  An exciting issue\n124  A boring issue\n",
+            ),
+            (
+                "{{range .}}{{.title}} ({{.labels | join \", \" | color \"yellow\"}}){{\"\\n\"}}{{end}}",
+                "An exciting issue (bug, help wanted)\nA boring issue ()\n",
+            ),
+        ];
+        for (template, want) in tests {
+            assert_eq!(render(template, &issues).unwrap(), want, "template {}", template);
+        }
+
+        // Text is written as it comes, while rows wait for `tablerender` or the end.
+        let pr = json!({
+            "number": 3519,
+            "title": "Add table and helper template functions",
+            "body": "Resolves #3488",
+            "assignees": [{"login": "mislav", "name": "Mislav Marohnić"}],
+            "reviews": [{"author": {"login": "mislav"}, "state": "COMMENTED", "body": "Looking good"}],
+        });
+        let template = r#"#{{.number}} {{.title}}
+
+{{.body}}
+
+{{tablerow "ASSIGNEE" "NAME"}}{{range .assignees}}{{tablerow .login .name}}{{end}}{{tablerender}}
+{{tablerow "REVIEWER" "STATE" "COMMENT"}}{{range .reviews}}{{tablerow .author.login .state .body}}{{end}}
+"#;
+        assert_eq!(
+            render(template, &pr).unwrap(),
+            r#"#3519 Add table and helper template functions
+
+Resolves #3488
+
+ASSIGNEE  NAME
+mislav    Mislav Marohnić
+
+
+REVIEWER  STATE      COMMENT
+mislav    COMMENTED  Looking good
+"#
+        );
+
+        // Like Go, range goes over the values of an object in the order of their keys.
+        assert_eq!(
+            render("{{range .}}{{.}} {{end}}", &json!({"b": 2, "a": 1})).unwrap(),
+            "1 2 "
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let value = json!({"name": "web", "time_created": "not a time"});
+
+        let tests = vec![
+            ("{{.name", "invalid template: unclosed action"),
+            ("{{\"name}}", "invalid template: unterminated string"),
+            ("{{if .name}}x", "invalid template: missing {{end}} for {{if}}"),
+            ("x{{end}}", "invalid template: unexpected {{end}}"),
+            ("{{tablerow (.name}}", "invalid template: missing )"),
+            ("{{.name)}}", "invalid template: unexpected )"),
+            ("{{.name |}}", "invalid template: missing value"),
+            ("{{1}}", "invalid template: unexpected 1"),
+            (
+                "{{.name \"x\"}}",
+                "can't give arguments to a value that is not a function",
+            ),
+            ("{{nope .name}}", "unknown function nope"),
+            ("{{color \"pink\" .name}}", "unknown color pink"),
+            ("{{timeago .time_created}}", "timeago can't parse not a time"),
+            ("{{range .name}}{{end}}", "range can't iterate over web"),
+        ];
+
+        for (template, want) in tests {
+            let err = render(template, &value).unwrap_err();
+            assert!(err.to_string().contains(want), "template {}: {}", template, err);
+        }
+    }
+
+    #[test]
+    fn test_template_timeago() {
+        let time = (chrono::Utc::now() - chrono::Duration::hours(2)).to_rfc3339();
+        assert_eq!(render("{{timeago .}}", &json!(time)).unwrap(), "2 hours ago");
+        assert_eq!(render("{{timeago .}}", &json!(null)).unwrap(), "");
+        assert_eq!(
+            render(
+                "{{tablerow .name (timeago .time)}}",
+                &json!({"name": "web", "time": time})
+            )
+            .unwrap(),
+            "web  2 hours ago\n"
+        );
+    }
+}
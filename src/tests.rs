@@ -297,9 +297,22 @@ async fn test_main(ctx: &mut MainContext) {
         TestItem {
             name: "api endpoint does not exist".to_string(),
             args: vec!["oxide".to_string(), "api".to_string(), "foo/bar".to_string()],
+            want_out: "\"request_id\"".to_string(),
+            want_err: "404 Not Found".to_string(),
+            want_code: 4,
+            ..Default::default()
+        },
+        TestItem {
+            name: "api endpoint does not exist silently".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "api".to_string(),
+                "foo/bar".to_string(),
+                "--silent".to_string(),
+            ],
             want_out: "".to_string(),
-            want_err: "404 Not Found Not Found".to_string(),
-            want_code: 1,
+            want_err: "404 Not Found".to_string(),
+            want_code: 4,
             ..Default::default()
         },
         TestItem {
@@ -317,6 +330,43 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 1,
             ..Default::default()
         },
+        TestItem {
+            name: "api list endpoints".to_string(),
+            args: vec!["oxide".to_string(), "api".to_string(), "--list".to_string()],
+            want_out: "  GET     /organizations".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "api describe an endpoint".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "api".to_string(),
+                "describe".to_string(),
+                "organization_create".to_string(),
+            ],
+            want_out: "POST /organizations\norganization_create: Create a new organization".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
+        TestItem {
+            name: "api fields that do not match the spec".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "api".to_string(),
+                "organizations".to_string(),
+                "-F".to_string(),
+                "name=zoo".to_string(),
+                "-F".to_string(),
+                "description=true".to_string(),
+            ],
+            want_out: "".to_string(),
+            want_err: "invalid request body: `description` must be a string".to_string(),
+            want_code: 2,
+            ..Default::default()
+        },
         TestItem {
             name: "list racks".to_string(),
             args: vec!["oxide".to_string(), "racks".to_string(), "list".to_string()],
@@ -353,6 +403,14 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "system update refresh".to_string(),
+            args: vec!["oxide".to_string(), "system".to_string(), "update".to_string(), "refresh".to_string()],
+            want_out: "✔ Refreshed update metadata for the rack at ".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         // For some reason this test keeps failing. The output on stdout looks exactly the same but the
         // test fails. I will skip for now
 //        TestItem {
@@ -544,6 +602,21 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "delete orgs matching nothing".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "org".to_string(),
+                "delete".to_string(),
+                "--filter".to_string(),
+                "dune-*".to_string(),
+                "--confirm".to_string(),
+            ],
+            want_out: "No organizations to delete".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "list orgs after delete".to_string(),
             args: vec!["oxide".to_string(), "org".to_string(), "list".to_string()],
@@ -1730,6 +1803,23 @@ async fn test_main(ctx: &mut MainContext) {
             want_code: 0,
             ..Default::default()
         },
+        TestItem {
+            name: "tree of a project".to_string(),
+            args: vec![
+                "oxide".to_string(),
+                "tree".to_string(),
+                "--organization".to_string(),
+                "maze-war".to_string(),
+                "--project".to_string(),
+                "development".to_string(),
+                "--depth".to_string(),
+                "2".to_string(),
+            ],
+            want_out: "maze-war\n└── development\n".to_string(),
+            want_err: "".to_string(),
+            want_code: 0,
+            ..Default::default()
+        },
         TestItem {
             name: "delete an instance".to_string(),
             args: vec![
@@ -1823,3 +1913,44 @@ async fn test_main(ctx: &mut MainContext) {
         }
     }
 }
+
+#[test]
+fn test_override_config() {
+    use clap::{CommandFactory, FromArgMatches};
+
+    let mut config = crate::config::new_blank_config().unwrap();
+    let mut c = crate::config_from_env::EnvConfig::inherit_env(&mut config);
+    let (io, _, _) = crate::iostreams::IoStreams::test();
+    let mut ctx = crate::context::Context {
+        config: &mut c,
+        io,
+        debug: false,
+    };
+
+    let args = ["oxide", "--as", "admin", "--timeout", "5s", "--retries", "2", "version"];
+    let matches = crate::Opts::command().try_get_matches_from(args).unwrap();
+    let opts = crate::Opts::from_arg_matches(&matches).unwrap();
+    crate::override_config(&mut ctx, &opts, &matches).unwrap();
+
+    let host = "https://oxide.example.com/";
+    let tests = vec![
+        ("user", "admin", "--as"),
+        ("http_timeout", "5s", "--timeout"),
+        ("retries", "2", "--retries"),
+    ];
+    for (key, value, source) in tests {
+        assert_eq!(
+            ctx.config.get_with_source(host, key).unwrap(),
+            (value.to_string(), source.to_string()),
+            "{}",
+            key
+        );
+    }
+
+    // An invalid value fails rather than being ignored.
+    let args = ["oxide", "--retries", "many", "version"];
+    let matches = crate::Opts::command().try_get_matches_from(args).unwrap();
+    let opts = crate::Opts::from_arg_matches(&matches).unwrap();
+    let err = crate::override_config(&mut ctx, &opts, &matches).unwrap_err();
+    assert_eq!(err.to_string(), "invalid --retries `many`, for example use `3`");
+}
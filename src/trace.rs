@@ -0,0 +1,375 @@
+use std::{io::Write, sync::Mutex};
+
+use anyhow::Result;
+
+/// The headers that hold credentials, which are never printed or written to a trace.
+const REDACTED_HEADERS: [&str; 3] = ["authorization", "cookie", "set-cookie"];
+
+/// Records the requests to the API and their responses, for `--verbose-http` and
+/// `--trace-file`.
+///
+/// Requests are recorded as they are sent, by `send`, which the gateway of the API
+/// clients and `oxide api` send every request with, so every retry is recorded too.
+/// With `--verbose-http` they are printed to standard error, and with `--trace-file`
+/// they are written to a HAR file once the command is done. Credentials are redacted
+/// in both.
+pub struct Tracer {
+    verbose: bool,
+    trace_file: String,
+    out: Mutex<Box<dyn Write + Send>>,
+    entries: Mutex<Vec<serde_json::Value>>,
+}
+
+/// A request and its response, or the error that kept the request from getting one.
+struct Exchange {
+    started_at: chrono::DateTime<chrono::Utc>,
+    time: std::time::Duration,
+    method: http::Method,
+    url: String,
+    headers: http::HeaderMap,
+    body: hyper::body::Bytes,
+    response: std::result::Result<(http::StatusCode, http::HeaderMap, hyper::body::Bytes), String>,
+}
+
+impl Tracer {
+    pub fn new(verbose: bool, trace_file: &str, out: Box<dyn Write + Send>) -> Self {
+        Tracer {
+            verbose,
+            trace_file: trace_file.to_string(),
+            out: Mutex::new(out),
+            entries: Default::default(),
+        }
+    }
+
+    /// Send a request with `http`, and record it with its response, or with the error
+    /// that kept it from getting one. The error is returned as it is, so it keeps its
+    /// kind, like being a timeout.
+    pub async fn send(&self, http: &reqwest::Client, req: reqwest::Request) -> reqwest::Result<reqwest::Response> {
+        let method = req.method().clone();
+        let url = req.url().to_string();
+        let headers = req.headers().clone();
+        let body = req
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(hyper::body::Bytes::copy_from_slice)
+            .unwrap_or_default();
+
+        let started_at = chrono::Utc::now();
+        let start = std::time::Instant::now();
+        let result = async {
+            let resp = http.execute(req).await?;
+            let status = resp.status();
+            let version = resp.version();
+            let headers = resp.headers().clone();
+            Ok::<_, reqwest::Error>((status, version, headers, resp.bytes().await?))
+        }
+        .await;
+
+        self.record(&Exchange {
+            started_at,
+            time: start.elapsed(),
+            method,
+            url,
+            headers,
+            body,
+            response: match &result {
+                Ok((status, _, headers, body)) => Ok((*status, headers.clone(), body.clone())),
+                Err(err) => Err(err.to_string()),
+            },
+        });
+
+        // The body was read to record it, so the response is put back together.
+        let (status, version, headers, body) = result?;
+        let mut resp = http::Response::new(body);
+        *resp.status_mut() = status;
+        *resp.version_mut() = version;
+        *resp.headers_mut() = headers;
+        Ok(resp.into())
+    }
+
+    fn record(&self, exchange: &Exchange) {
+        if self.verbose {
+            let mut out = self.out.lock().unwrap();
+            // Printing the trace should never fail the request.
+            let _ = out.write_all(exchange.dump().as_bytes());
+            let _ = out.flush();
+        }
+
+        if !self.trace_file.is_empty() {
+            self.entries.lock().unwrap().push(exchange.har_entry());
+        }
+    }
+
+    /// Write the HAR file of the requests, with `--trace-file`.
+    pub fn finish(&self) -> Result<()> {
+        if self.trace_file.is_empty() {
+            return Ok(());
+        }
+
+        let har = serde_json::json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": "oxide",
+                    "version": clap::crate_version!(),
+                },
+                "entries": self.entries.lock().unwrap().clone(),
+            }
+        });
+
+        std::fs::write(&self.trace_file, serde_json::to_string_pretty(&har)?)
+            .map_err(|err| anyhow::anyhow!("could not write the trace to {}: {}", self.trace_file, err))
+    }
+}
+
+impl Exchange {
+    /// The request and response as text, like:
+    ///
+    ///     > GET https://oxide.example.com/organizations
+    ///     > authorization: [redacted]
+    ///     < 200 OK (34ms)
+    ///     < content-type: application/json
+    ///     <
+    ///     < {"items":[],"next_page":null}
+    fn dump(&self) -> String {
+        let mut lines = vec![format!("> {} {}", self.method, self.url)];
+        lines.extend(redact_headers(&self.headers).map(|(name, value)| format!("> {}: {}", name, value)));
+        if !self.body.is_empty() {
+            lines.push(">".to_string());
+            lines.extend(
+                String::from_utf8_lossy(&self.body)
+                    .lines()
+                    .map(|line| format!("> {}", line)),
+            );
+        }
+
+        match &self.response {
+            Ok((status, response_headers, body)) => {
+                lines.push(format!("< {} ({}ms)", status, self.time.as_millis()));
+                lines.extend(redact_headers(response_headers).map(|(name, value)| format!("< {}: {}", name, value)));
+                if !body.is_empty() {
+                    lines.push("<".to_string());
+                    lines.extend(String::from_utf8_lossy(body).lines().map(|line| format!("< {}", line)));
+                }
+            }
+            Err(err) => lines.push(format!("< error ({}ms): {}", self.time.as_millis(), err)),
+        }
+
+        format!("{}\n\n", lines.join("\n"))
+    }
+
+    /// The request and response as an entry of a HAR file.
+    fn har_entry(&self) -> serde_json::Value {
+        let query: Vec<serde_json::Value> = reqwest::Url::parse(&self.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| serde_json::json!({ "name": name, "value": value }))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut request = serde_json::json!({
+            "method": self.method.as_str(),
+            "url": self.url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": har_headers(&self.headers),
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": self.body.len(),
+        });
+        if !self.body.is_empty() {
+            request["postData"] = serde_json::json!({
+                "mimeType": mime_type(&self.headers),
+                "text": String::from_utf8_lossy(&self.body),
+            });
+        }
+
+        let response = match &self.response {
+            Ok((status, headers, body)) => serde_json::json!({
+                "status": status.as_u16(),
+                "statusText": status.canonical_reason().unwrap_or_default(),
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": har_headers(headers),
+                "content": {
+                    "size": body.len(),
+                    "mimeType": mime_type(headers),
+                    "text": String::from_utf8_lossy(body),
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body.len(),
+            }),
+            // HAR has no place for requests without a response, the convention is a
+            // status of 0 with the error as the status text.
+            Err(err) => serde_json::json!({
+                "status": 0,
+                "statusText": err,
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "content": { "size": 0, "mimeType": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+            }),
+        };
+
+        let time = self.time.as_secs_f64() * 1000.0;
+        serde_json::json!({
+            "startedDateTime": self.started_at.to_rfc3339(),
+            "time": time,
+            "request": request,
+            "response": response,
+            "cache": {},
+            "timings": { "send": 0, "wait": time, "receive": 0 },
+        })
+    }
+}
+
+/// Send a request with `http`, recording it if there is a tracer, see `Tracer::send`.
+pub async fn send(
+    tracer: Option<&Tracer>,
+    http: &reqwest::Client,
+    req: reqwest::Request,
+) -> reqwest::Result<reqwest::Response> {
+    match tracer {
+        Some(tracer) => tracer.send(http, req).await,
+        None => http.execute(req).await,
+    }
+}
+
+/// The headers with their values as text, and credentials redacted.
+pub fn redact_headers(headers: &http::HeaderMap) -> impl Iterator<Item = (&str, String)> {
+    headers.iter().map(|(name, value)| {
+        let value = if REDACTED_HEADERS.contains(&name.as_str()) {
+            "[redacted]".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).to_string()
+        };
+        (name.as_str(), value)
+    })
+}
+
+fn har_headers(h: &http::HeaderMap) -> Vec<serde_json::Value> {
+    redact_headers(h)
+        .map(|(name, value)| serde_json::json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn mime_type(headers: &http::HeaderMap) -> String {
+    headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// A writer that can be read back after it was handed to the tracer.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tracer() {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = tokio::io::BufReader::new(stream);
+            let mut line = String::new();
+            while stream.read_line(&mut line).await.unwrap() > 2 {
+                line.clear();
+            }
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: session=SECRET\r\n\
+                      Content-Length: 13\r\n\r\n{\"items\":[]}\n",
+                )
+                .await
+                .unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let trace_file = dir.path().join("out.har");
+        let out = Buffer::default();
+        let tracer = Tracer::new(true, trace_file.to_str().unwrap(), Box::new(out.clone()));
+
+        let http = reqwest::Client::new();
+        let req = http
+            .get(format!("{}organizations?limit=10", host))
+            .bearer_auth("MY_TOKEN")
+            .build()
+            .unwrap();
+        let resp = tracer.send(&http, req).await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.text().await.unwrap(), "{\"items\":[]}\n");
+
+        let dump = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            dump.starts_with(&format!("> GET {}organizations?limit=10\n", host)),
+            "{}",
+            dump
+        );
+        assert!(dump.contains("> authorization: [redacted]\n"), "{}", dump);
+        assert!(dump.contains("< 200 OK ("), "{}", dump);
+        assert!(dump.contains("< set-cookie: [redacted]\n"), "{}", dump);
+        assert!(dump.contains("<\n< {\"items\":[]}\n"), "{}", dump);
+        assert!(!dump.contains("MY_TOKEN") && !dump.contains("SECRET"), "{}", dump);
+
+        tracer.finish().unwrap();
+        let har = std::fs::read_to_string(&trace_file).unwrap();
+        assert!(!har.contains("MY_TOKEN") && !har.contains("SECRET"), "{}", har);
+
+        let har: serde_json::Value = serde_json::from_str(&har).unwrap();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["request"]["method"], "GET");
+        assert_eq!(
+            entries[0]["request"]["queryString"],
+            serde_json::json!([{"name": "limit", "value": "10"}])
+        );
+        assert_eq!(entries[0]["response"]["status"], 200);
+        assert_eq!(entries[0]["response"]["content"]["text"], "{\"items\":[]}\n");
+    }
+
+    #[tokio::test]
+    async fn test_tracer_unreachable() {
+        // Nothing listens on this port once the listener is dropped.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+
+        let out = Buffer::default();
+        let tracer = Tracer::new(true, "", Box::new(out.clone()));
+
+        // The error is the one of the request, so it keeps its kind.
+        let http = reqwest::Client::new();
+        let err = tracer.send(&http, http.get(&host).build().unwrap()).await.unwrap_err();
+        assert!(err.is_connect(), "{}", err);
+
+        let dump = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        assert!(dump.contains("< error ("), "{}", dump);
+    }
+}
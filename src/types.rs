@@ -6,6 +6,8 @@ pub enum FormatOutput {
     Json,
     Yaml,
     Table,
+    Csv,
+    Tsv,
 }
 
 impl Default for FormatOutput {
@@ -16,6 +18,156 @@ impl Default for FormatOutput {
 
 impl FormatOutput {
     pub fn variants() -> Vec<String> {
-        vec!["table".to_string(), "json".to_string(), "yaml".to_string()]
+        vec![
+            "table".to_string(),
+            "json".to_string(),
+            "yaml".to_string(),
+            "csv".to_string(),
+            "tsv".to_string(),
+        ]
+    }
+}
+
+/// How long to wait between refreshes, written as a number followed by `ms`, `s`, `m`,
+/// or `h`, for example `500ms` or `2s`. A number on its own is in seconds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval(pub std::time::Duration);
+
+impl Default for Interval {
+    fn default() -> Interval {
+        Interval(std::time::Duration::from_secs(2))
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        let invalid = || anyhow::anyhow!("invalid interval `{}`, for example use `2s`", s);
+        let number: u64 = number.parse().map_err(|_| invalid())?;
+
+        let duration = match unit {
+            "ms" => std::time::Duration::from_millis(number),
+            "" | "s" => std::time::Duration::from_secs(number),
+            "m" => std::time::Duration::from_secs(number.checked_mul(60).ok_or_else(invalid)?),
+            "h" => std::time::Duration::from_secs(number.checked_mul(60 * 60).ok_or_else(invalid)?),
+            _ => anyhow::bail!("invalid unit `{}` in interval `{}`, use ms, s, m, or h", unit, s),
+        };
+
+        if duration.is_zero() {
+            anyhow::bail!("interval must be greater than 0");
+        }
+
+        Ok(Interval(duration))
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let millis = self.0.as_millis();
+        if millis % 1000 != 0 {
+            write!(f, "{}ms", millis)
+        } else if millis % (60 * 60 * 1000) == 0 {
+            write!(f, "{}h", millis / (60 * 60 * 1000))
+        } else if millis % (60 * 1000) == 0 {
+            write!(f, "{}m", millis / (60 * 1000))
+        } else {
+            write!(f, "{}s", millis / 1000)
+        }
+    }
+}
+
+/// Picks resources by their names, written as a glob like `test-*`, or as a regex
+/// between slashes like `/^test-\d+$/`.
+#[derive(Debug, Clone)]
+pub struct NameFilter(regex::Regex);
+
+impl NameFilter {
+    pub fn is_match(&self, name: &str) -> bool {
+        self.0.is_match(name)
+    }
+}
+
+impl std::str::FromStr for NameFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() > 1 && s.starts_with('/') && s.ends_with('/') {
+            let regex = regex::Regex::new(&s[1..s.len() - 1])
+                .map_err(|err| anyhow::anyhow!("invalid regex `{}`: {}", s, err))?;
+            return Ok(NameFilter(regex));
+        }
+
+        // A glob matches the whole name, where `*` is any text and `?` any character.
+        let mut pattern = "^".to_string();
+        for c in s.chars() {
+            match c {
+                '*' => pattern.push_str(".*"),
+                '?' => pattern.push('.'),
+                _ => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+
+        Ok(NameFilter(regex::Regex::new(&pattern)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        let tests = vec![
+            ("500ms", 500, "500ms"),
+            ("2s", 2000, "2s"),
+            ("3", 3000, "3s"),
+            ("90s", 90000, "90s"),
+            ("1m", 60000, "1m"),
+            ("2h", 7200000, "2h"),
+        ];
+
+        for (input, millis, display) in tests {
+            let interval: Interval = input.parse().unwrap();
+            assert_eq!(interval.0.as_millis(), millis, "interval {}", input);
+            assert_eq!(interval.to_string(), display, "interval {}", input);
+        }
+
+        assert!("0s".parse::<Interval>().is_err());
+        assert!("2d".parse::<Interval>().is_err());
+        assert!("s".parse::<Interval>().is_err());
+
+        let err = "18446744073709551615h".parse::<Interval>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid interval `18446744073709551615h`, for example use `2s`"
+        );
+        assert!("18446744073709551615m".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn test_name_filter() {
+        let tests = vec![
+            ("test-*", "test-disk-1", true),
+            ("test-*", "prod-test-disk", false),
+            ("disk-?", "disk-1", true),
+            ("disk-?", "disk-10", false),
+            ("disk.1", "disk-1", false),
+            ("/^test-\\d+$/", "test-42", true),
+            ("/^test-\\d+$/", "test-disk", false),
+            ("/disk/", "my-disk-1", true),
+        ];
+
+        for (filter, name, want) in tests {
+            let filter: NameFilter = filter.parse().unwrap();
+            assert_eq!(filter.is_match(name), want, "filter {:?} on {}", filter, name);
+        }
+
+        assert!("/disk(/".parse::<NameFilter>().is_err());
     }
 }
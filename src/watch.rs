@@ -0,0 +1,282 @@
+use anyhow::Result;
+
+/// Refreshes the output of a list or view command with `--watch`.
+///
+/// On a terminal the output is redrawn in place, and table rows that changed
+/// since the last refresh are highlighted. Otherwise every change is written as
+/// a line of json like `{"type":"modified","object":{...}}`, with a type of
+/// `added`, `modified`, or `deleted`, so the output can be followed by other
+/// tools.
+///
+/// Without `--watch` the output is written once, as usual. This is meant to
+/// drive a loop:
+///
+///     let mut watch = crate::watch::Watch::new(self.watch, &self.interval);
+///     while watch.tick().await {
+///         let result = ...;
+///         watch.write_output(&mut ctx.io, &format, &result)?;
+///     }
+pub struct Watch {
+    enabled: bool,
+    interval: crate::types::Interval,
+    ticks: u64,
+    previous: std::collections::BTreeMap<String, serde_json::Value>,
+    previous_fields: Option<Vec<String>>,
+}
+
+impl Watch {
+    pub fn new(enabled: bool, interval: &crate::types::Interval) -> Self {
+        Watch {
+            enabled,
+            interval: interval.clone(),
+            ticks: 0,
+            previous: Default::default(),
+            previous_fields: None,
+        }
+    }
+
+    /// Wait for the next refresh. This returns true right away the first time, and
+    /// after the interval if watching.
+    pub async fn tick(&mut self) -> bool {
+        self.ticks += 1;
+        if self.ticks == 1 {
+            return true;
+        }
+
+        if !self.enabled {
+            return false;
+        }
+
+        tokio::time::sleep(self.interval.0).await;
+        true
+    }
+
+    pub fn write_output_for_vec<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        io: &mut crate::iostreams::IoStreams,
+        format: &crate::types::FormatOutput,
+        value: &[T],
+    ) -> Result<()> {
+        if !self.enabled {
+            return io.write_output_for_vec(format, value);
+        }
+
+        let current = value
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let json = serde_json::to_value(v)?;
+                Ok((key(&json, i), json))
+            })
+            .collect::<Result<Vec<(String, serde_json::Value)>>>()?;
+
+        if io.is_stdout_tty() {
+            self.clear(io)?;
+            if io.is_table_output(format) {
+                let highlight: Vec<bool> = current
+                    .iter()
+                    .map(|(key, json)| self.ticks > 1 && self.previous.get(key) != Some(json))
+                    .collect();
+                io.write_output_table_for_vec_highlighted(value, &highlight)?;
+            } else {
+                io.write_output_for_vec(format, value)?;
+            }
+        } else {
+            self.write_events(io, &current)?;
+        }
+
+        self.previous = current.into_iter().collect();
+
+        Ok(())
+    }
+
+    pub fn write_output<T: serde::Serialize + tabled::Tabled>(
+        &mut self,
+        io: &mut crate::iostreams::IoStreams,
+        format: &crate::types::FormatOutput,
+        value: &T,
+    ) -> Result<()> {
+        if !self.enabled {
+            return io.write_output(format, value);
+        }
+
+        let json = serde_json::to_value(value)?;
+        let current = vec![(key(&json, 0), json)];
+
+        if io.is_stdout_tty() {
+            self.clear(io)?;
+            if io.is_table_output(format) {
+                io.write_output_table_highlighted(value, self.previous_fields.as_deref())?;
+            } else {
+                io.write_output(format, value)?;
+            }
+            self.previous_fields = Some(value.fields());
+        } else {
+            self.write_events(io, &current)?;
+        }
+
+        self.previous = current.into_iter().collect();
+
+        Ok(())
+    }
+
+    /// Clear the terminal and print how often it is refreshed.
+    fn clear(&self, io: &mut crate::iostreams::IoStreams) -> Result<()> {
+        let cs = io.color_scheme();
+        write!(io.out, "\x1b[H\x1b[2J")?;
+        writeln!(
+            io.out,
+            "{}\n",
+            cs.gray(&format!("Every {}, press Ctrl+C to stop.", self.interval))
+        )?;
+
+        Ok(())
+    }
+
+    /// Write a line of json for every value that was added, modified, or deleted since
+    /// the last refresh.
+    fn write_events(
+        &self,
+        io: &mut crate::iostreams::IoStreams,
+        current: &[(String, serde_json::Value)],
+    ) -> Result<()> {
+        let mut events = Vec::new();
+        for (key, json) in current {
+            match self.previous.get(key) {
+                None => events.push(("added", json)),
+                Some(previous) if previous != json => events.push(("modified", json)),
+                Some(_) => {}
+            }
+        }
+
+        let keys: std::collections::HashSet<&String> = current.iter().map(|(key, _)| key).collect();
+        for (key, json) in &self.previous {
+            if !keys.contains(key) {
+                events.push(("deleted", json));
+            }
+        }
+
+        for (kind, object) in events {
+            writeln!(io.out, "{}", serde_json::json!({ "type": kind, "object": object }))?;
+        }
+        io.out.flush()?;
+
+        Ok(())
+    }
+}
+
+/// The key to tell values apart between refreshes. Most resources have an ID, the ones
+/// that don't are told apart by their position.
+fn key(json: &serde_json::Value, index: usize) -> String {
+    match json.get("id") {
+        Some(serde_json::Value::String(id)) => id.to_string(),
+        _ => index.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+    use tabled::Tabled;
+
+    use super::*;
+
+    #[derive(serde::Serialize, Tabled)]
+    struct Instance {
+        id: String,
+        name: String,
+        run_state: String,
+    }
+
+    fn instance(id: &str, run_state: &str) -> Instance {
+        Instance {
+            id: id.to_string(),
+            name: format!("instance-{}", id),
+            run_state: run_state.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_events() {
+        let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        io.set_stdout_tty(false);
+
+        let format = crate::types::FormatOutput::Table;
+        let mut watch = Watch::new(true, &"1ms".parse().unwrap());
+
+        assert!(watch.tick().await);
+        watch
+            .write_output_for_vec(&mut io, &format, &[instance("1", "starting"), instance("2", "running")])
+            .unwrap();
+        assert!(watch.tick().await);
+        watch
+            .write_output_for_vec(&mut io, &format, &[instance("1", "running"), instance("3", "starting")])
+            .unwrap();
+        assert!(watch.tick().await);
+        watch
+            .write_output_for_vec(&mut io, &format, &[instance("1", "running"), instance("3", "starting")])
+            .unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        let events: Vec<String> = stdout
+            .lines()
+            .map(|line| {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                format!("{} {}", event["type"], event["object"]["id"])
+            })
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                r#""added" "1""#,
+                r#""added" "2""#,
+                r#""modified" "1""#,
+                r#""added" "3""#,
+                r#""deleted" "2""#,
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_watch_disabled() {
+        let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        io.set_stdout_tty(true);
+        io.set_color_enabled(false);
+
+        let format = crate::types::FormatOutput::Json;
+        let mut watch = Watch::new(false, &Default::default());
+
+        let mut refreshes = 0;
+        while watch.tick().await {
+            watch.write_output(&mut io, &format, &instance("1", "running")).unwrap();
+            refreshes += 1;
+        }
+
+        assert_eq!(refreshes, 1);
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert!(stdout.starts_with("{\n  \"id\": \"1\""), "{}", stdout);
+    }
+
+    #[tokio::test]
+    async fn test_watch_terminal() {
+        let (mut io, stdout_path, _) = crate::iostreams::IoStreams::test();
+        io.set_stdout_tty(true);
+        io.set_color_enabled(false);
+
+        let format = crate::types::FormatOutput::Table;
+        let mut watch = Watch::new(true, &"1ms".parse().unwrap());
+
+        assert!(watch.tick().await);
+        watch
+            .write_output(&mut io, &format, &instance("1", "starting"))
+            .unwrap();
+
+        let stdout = std::fs::read_to_string(stdout_path).unwrap();
+        assert_eq!(
+            stdout,
+            "\x1b[H\x1b[2JEvery 1ms, press Ctrl+C to stop.\n\n id        | 1          \n name      | instance-1 \n run_state \
+             | starting   \n"
+        );
+    }
+}